@@ -0,0 +1,34 @@
+use std::hint::black_box;
+use std::time::Instant;
+
+use ble_temperature_ui::decode::TemperatureMeasurement;
+use ble_temperature_ui::history::TemperatureHistory;
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+/// 23.45°C as sent by the sensor
+const NOTIFICATION: [u8; 5] = [0x00, 0x9a, 0x5b, 0x00, 0xfe];
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("health_thermometer", |b| {
+        b.iter(|| TemperatureMeasurement::try_from(black_box(&NOTIFICATION[..])))
+    });
+    group.finish();
+}
+
+fn history(c: &mut Criterion) {
+    let mut group = c.benchmark_group("history");
+    group.throughput(Throughput::Elements(1));
+
+    let mut history = TemperatureHistory::with_capacity(300);
+    let now = Instant::now();
+    group.bench_function("push", |b| b.iter(|| history.push(now, black_box(23.45))));
+
+    // full at the default history length after the push benchmark
+    group.bench_function("mean", |b| b.iter(|| black_box(&history).mean()));
+    group.finish();
+}
+
+criterion_group!(benches, decode, history);
+criterion_main!(benches);
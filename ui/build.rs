@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+use resvg::{tiny_skia, usvg};
+
+/// Window icon edge length in pixels
+const ICON_SIZE: u32 = 32;
+
+/// Render the SVG window icon into `$OUT_DIR/icon.rgba`, unmultiplied RGBA
+/// as `egui::IconData` expects
+fn main() {
+    let svg = "assets/thermometer.svg";
+    println!("cargo:rerun-if-changed={}", svg);
+
+    let data = std::fs::read(svg).expect("icon svg is readable");
+    let tree = usvg::Tree::from_data(&data, &usvg::Options::default()).expect("icon svg is valid");
+
+    let mut pixmap = tiny_skia::Pixmap::new(ICON_SIZE, ICON_SIZE).unwrap();
+    let size = tree.size();
+    let transform = tiny_skia::Transform::from_scale(
+        ICON_SIZE as f32 / size.width(),
+        ICON_SIZE as f32 / size.height(),
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    let rgba: Vec<u8> = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("icon.rgba");
+    std::fs::write(out, rgba).expect("OUT_DIR is writable");
+}
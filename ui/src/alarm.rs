@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+/// Oldest alarms are dropped beyond this many entries
+const MAX_EVENTS: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[non_exhaustive]
+pub enum AlarmKind {
+    High,
+    Low,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug)]
+pub struct AlarmEvent {
+    pub timestamp: SystemTime,
+    pub celsius: f32,
+    pub kind: AlarmKind,
+}
+
+/// Fires once the temperature rises above `high`, and again only after it
+/// dropped below `high - hysteresis`, so jitter around `high` fires once
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TemperatureThreshold {
+    pub high: f32,
+    pub hysteresis: f32,
+    armed: bool,
+}
+
+impl TemperatureThreshold {
+    pub const DEFAULT_HYSTERESIS: f32 = 0.5;
+
+    pub fn new(high: f32, hysteresis: f32) -> Self {
+        Self {
+            high,
+            hysteresis,
+            armed: true,
+        }
+    }
+
+    /// Whether `celsius` fires the alarm
+    pub fn check(&mut self, celsius: f32) -> bool {
+        if self.armed && celsius > self.high {
+            self.armed = false;
+            return true;
+        }
+        if celsius < self.high - self.hysteresis {
+            self.armed = true;
+        }
+        false
+    }
+}
+
+/// Check `threshold` against the current `limit`, keeping its state while
+/// the limit stays set
+fn crossed(
+    threshold: &mut Option<TemperatureThreshold>,
+    limit: Option<f32>,
+    hysteresis: f32,
+    celsius: f32,
+) -> bool {
+    let Some(limit) = limit else {
+        *threshold = None;
+        return false;
+    };
+    let threshold = threshold.get_or_insert_with(|| TemperatureThreshold::new(limit, hysteresis));
+    threshold.high = limit;
+    threshold.hysteresis = hysteresis;
+    threshold.check(celsius)
+}
+
+/// Threshold breaches of the session, logged once per breach
+#[derive(Default)]
+pub struct AlarmLog {
+    pub events: VecDeque<AlarmEvent>,
+    high: Option<TemperatureThreshold>,
+    /// On the negated temperature, as a low limit is a high limit of that
+    low: Option<TemperatureThreshold>,
+}
+
+impl AlarmLog {
+    /// Record an event when `celsius` starts breaching a threshold,
+    /// returning the kind of the new alarm. An alarm clears once the
+    /// temperature is `hysteresis` back inside its threshold
+    pub fn check(
+        &mut self,
+        celsius: f32,
+        high: Option<f32>,
+        low: Option<f32>,
+        hysteresis: f32,
+    ) -> Option<AlarmKind> {
+        // both are checked to keep their state current
+        let high = crossed(&mut self.high, high, hysteresis, celsius);
+        let low = crossed(&mut self.low, low.map(|low| -low), hysteresis, -celsius);
+        let fired = if high {
+            Some(AlarmKind::High)
+        } else if low {
+            Some(AlarmKind::Low)
+        } else {
+            None
+        };
+
+        if let Some(kind) = fired {
+            if self.events.len() >= MAX_EVENTS {
+                self.events.pop_front();
+            }
+            self.events.push_back(AlarmEvent {
+                timestamp: SystemTime::now(),
+                celsius,
+                kind,
+            });
+        }
+
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jitter_around_the_threshold_fires_once() {
+        let mut threshold =
+            TemperatureThreshold::new(27.0, TemperatureThreshold::DEFAULT_HYSTERESIS);
+        let fired: Vec<bool> = [26.9, 27.1, 26.9, 27.2, 26.8, 27.1]
+            .into_iter()
+            .map(|celsius| threshold.check(celsius))
+            .collect();
+        assert_eq!(fired, [false, true, false, false, false, false]);
+
+        // re-armed below 26.5
+        assert!(!threshold.check(26.4));
+        assert!(threshold.check(27.1));
+    }
+
+    #[test]
+    fn low_alarms_clear_above_the_hysteresis() {
+        let mut log = AlarmLog::default();
+        let kinds: Vec<_> = [15.5, 14.9, 15.1, 14.8, 15.6, 14.9]
+            .into_iter()
+            .map(|celsius| log.check(celsius, Some(30.0), Some(15.0), 0.5))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                None,
+                Some(AlarmKind::Low),
+                None,
+                None,
+                None,
+                Some(AlarmKind::Low)
+            ]
+        );
+        assert_eq!(log.events.len(), 2);
+    }
+}
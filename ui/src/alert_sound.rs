@@ -0,0 +1,120 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use log::warn;
+use rodio::source::{SineWave, Source};
+use rodio::{OutputStream, Sink};
+use serde::{Deserialize, Serialize};
+
+use crate::alarm::AlarmKind;
+
+/// Minimum time between two alert sounds
+const COOLDOWN: Duration = Duration::from_secs(5 * 60);
+
+/// `[alert_sound]` section of the config
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertSoundConfig {
+    pub enabled: bool,
+    pub high_freq_hz: f32,
+    pub low_freq_hz: f32,
+    pub duration_ms: u32,
+}
+
+impl Default for AlertSoundConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            high_freq_hz: 880.0,
+            low_freq_hz: 440.0,
+            duration_ms: 500,
+        }
+    }
+}
+
+/// Beeps when a reading raises an alarm
+pub struct TemperatureAlertSoundPlayer {
+    config: AlertSoundConfig,
+    last_played: Option<Instant>,
+}
+
+impl TemperatureAlertSoundPlayer {
+    pub fn new(config: AlertSoundConfig) -> Self {
+        Self {
+            config,
+            last_played: None,
+        }
+    }
+
+    /// Beep for an alarm raised by `TemperatureSession::record`
+    pub fn on_alarm(&mut self, kind: AlarmKind) {
+        let Some(freq) = self.tone(kind, Instant::now()) else {
+            return;
+        };
+
+        let duration = Duration::from_millis(self.config.duration_ms.into());
+        std::thread::spawn(move || {
+            if let Err(e) = play_tone(freq, duration) {
+                warn!("failed to play alert sound: {}", e);
+            }
+        });
+    }
+
+    /// Frequency to play for `kind` at `now`, `None` while disabled or cooling down
+    fn tone(&mut self, kind: AlarmKind, now: Instant) -> Option<f32> {
+        if !self.config.enabled {
+            return None;
+        }
+        if self
+            .last_played
+            .is_some_and(|at| now.duration_since(at) < COOLDOWN)
+        {
+            return None;
+        }
+        self.last_played = Some(now);
+
+        Some(match kind {
+            AlarmKind::High => self.config.high_freq_hz,
+            AlarmKind::Low => self.config.low_freq_hz,
+        })
+    }
+}
+
+fn play_tone(freq: f32, duration: Duration) -> Result<(), Box<dyn Error>> {
+    let (_stream, handle) = OutputStream::try_default()?;
+    let sink = Sink::try_new(&handle)?;
+    sink.append(SineWave::new(freq).take_duration(duration).amplify(0.2));
+    sink.sleep_until_end();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(enabled: bool) -> TemperatureAlertSoundPlayer {
+        TemperatureAlertSoundPlayer::new(AlertSoundConfig {
+            enabled,
+            ..AlertSoundConfig::default()
+        })
+    }
+
+    #[test]
+    fn alarms_beep_once_per_cooldown() {
+        let mut player = player(true);
+        let start = Instant::now();
+
+        assert_eq!(player.tone(AlarmKind::High, start), Some(880.0));
+        assert_eq!(
+            player.tone(AlarmKind::Low, start + Duration::from_secs(60)),
+            None
+        );
+        assert_eq!(player.tone(AlarmKind::Low, start + COOLDOWN), Some(440.0));
+        assert_eq!(player.tone(AlarmKind::High, start + COOLDOWN), None);
+    }
+
+    #[test]
+    fn disabled_sound_stays_silent() {
+        assert_eq!(player(false).tone(AlarmKind::High, Instant::now()), None);
+    }
+}
@@ -0,0 +1,58 @@
+//! Which btleplug backend this build talks to.
+//!
+//! btleplug picks its backend from the target platform; the `backend-*`
+//! features only record which platform a build expects. A mismatch is warned
+//! about at startup rather than failing the build, so `--all-features` still
+//! builds everywhere.
+
+/// Name of the BLE backend compiled into this binary
+pub fn active_backend() -> &'static str {
+    if cfg!(target_os = "linux") {
+        "BlueZ"
+    } else if cfg!(any(target_os = "macos", target_os = "ios")) {
+        "CoreBluetooth"
+    } else if cfg!(target_os = "windows") {
+        "WinRT"
+    } else if cfg!(target_os = "android") {
+        "Android"
+    } else {
+        "unknown"
+    }
+}
+
+/// Backend features, whether each is enabled and whether it matches the target
+const FEATURES: [(&str, bool, bool); 3] = [
+    (
+        "backend-bluez",
+        cfg!(feature = "backend-bluez"),
+        cfg!(target_os = "linux"),
+    ),
+    (
+        "backend-corebluetooth",
+        cfg!(feature = "backend-corebluetooth"),
+        cfg!(any(target_os = "macos", target_os = "ios")),
+    ),
+    (
+        "backend-winrt",
+        cfg!(feature = "backend-winrt"),
+        cfg!(target_os = "windows"),
+    ),
+];
+
+/// Backend features enabled in this build
+pub fn enabled_features() -> Vec<&'static str> {
+    FEATURES
+        .iter()
+        .filter(|(_, enabled, _)| *enabled)
+        .map(|(name, _, _)| *name)
+        .collect()
+}
+
+/// Backend features enabled in this build but meant for another platform
+pub fn mismatched_features() -> Vec<&'static str> {
+    FEATURES
+        .iter()
+        .filter(|(_, enabled, on_target)| *enabled && !*on_target)
+        .map(|(name, _, _)| *name)
+        .collect()
+}
@@ -0,0 +1,60 @@
+use btleplug::api::{BDAddr, ParseBDAddrError};
+
+/// One spelling of addresses in logs, the UI and on the command line
+pub trait BdAddrExt: Sized {
+    /// `"AA:BB:CC:DD:EE:FF"`
+    fn to_colon_hex(&self) -> String;
+
+    /// Parse `AA:BB:CC:DD:EE:FF`, in either case
+    fn from_colon_hex(s: &str) -> Result<Self, ParseBDAddrError>;
+}
+
+impl BdAddrExt for BDAddr {
+    fn to_colon_hex(&self) -> String {
+        let hex: Vec<String> = self
+            .into_inner()
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        hex.join(":")
+    }
+
+    fn from_colon_hex(s: &str) -> Result<Self, ParseBDAddrError> {
+        // two digits per byte, which from_str_delim does not insist on
+        if s.split(':').any(|part| part.len() != 2) {
+            return Err(ParseBDAddrError::IncorrectByteCount);
+        }
+        BDAddr::from_str_delim(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colon_hex_round_trips() {
+        let address = BDAddr::from([0xaa, 0xbb, 0x0c, 0x0d, 0xe0, 0xff]);
+        assert_eq!(address.to_colon_hex(), "AA:BB:0C:0D:E0:FF");
+        assert_eq!(BDAddr::from_colon_hex(&address.to_colon_hex()), Ok(address));
+        assert_eq!(BDAddr::from_colon_hex("aa:bb:0c:0d:e0:ff"), Ok(address));
+        assert_eq!(BDAddr::default().to_colon_hex(), "00:00:00:00:00:00");
+    }
+
+    #[test]
+    fn malformed_addresses_are_rejected() {
+        for s in [
+            "",
+            "AA:BB:CC:DD:EE",
+            "AA:BB:CC:DD:EE:FF:00",
+            "A:BB:CC:DD:EE:FF",
+            "AABBCCDDEEFF",
+        ] {
+            assert!(BDAddr::from_colon_hex(s).is_err(), "{}", s);
+        }
+        assert!(matches!(
+            BDAddr::from_colon_hex("GG:BB:CC:DD:EE:FF"),
+            Err(ParseBDAddrError::InvalidDigit(_))
+        ));
+    }
+}
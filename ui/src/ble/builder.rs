@@ -0,0 +1,214 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use uuid::Uuid;
+
+use super::{AddressType, DeviceFilter, EventBus, StreamStats, TemperatureSendor};
+use crate::ble_log::BleEventLog;
+use crate::decode::{
+    DecoderKind, HealthThermometerDecoder, SessionDecoder, TemperatureDecoder,
+    TEMPERATURE_MEASUREMENT,
+};
+use crate::metrics::Metrics;
+use crate::simulate::{InjectErrors, SimulatedSensor};
+use crate::sink::CsvSink;
+
+/// Options of the sensor task, starting from the defaults of `Config`
+pub struct TemperatureSendorBuilder {
+    device_filter: DeviceFilter,
+    scan_duration: Duration,
+    connect_timeout: Duration,
+    max_retries: u32,
+    decoder: Option<DecoderKind>,
+    calibration_offset: f32,
+    rssi_minimum: Option<i16>,
+    notify_char_uuid: Uuid,
+    address_type: AddressType,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_reconnects: Option<u32>,
+    idle_timeout: Option<Duration>,
+    notify_instant: Arc<Mutex<Option<Instant>>>,
+    metrics: Arc<Metrics>,
+    ble_log: Option<BleEventLog>,
+    csv_log: Option<CsvSink<BufWriter<File>>>,
+    simulated: Option<SimulatedSensor>,
+    inject_errors: Option<f64>,
+}
+
+impl TemperatureSendorBuilder {
+    pub fn new() -> Self {
+        Self {
+            device_filter: DeviceFilter::ByName(
+                Regex::new("(?i)temperature").expect("default name filter is valid"),
+            ),
+            scan_duration: Duration::from_secs(5),
+            connect_timeout: Duration::from_secs(15),
+            max_retries: 3,
+            decoder: None,
+            calibration_offset: 0.0,
+            rssi_minimum: None,
+            notify_char_uuid: TEMPERATURE_MEASUREMENT,
+            address_type: AddressType::Auto,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_reconnects: None,
+            idle_timeout: None,
+            notify_instant: Arc::default(),
+            metrics: Arc::default(),
+            ble_log: None,
+            csv_log: None,
+            simulated: None,
+            inject_errors: None,
+        }
+    }
+
+    pub fn device_filter(&mut self, filter: DeviceFilter) -> &mut Self {
+        self.device_filter = filter;
+        self
+    }
+
+    /// How long each scan runs before looking for the sensor
+    pub fn scan_duration(&mut self, duration: Duration) -> &mut Self {
+        self.scan_duration = duration;
+        self
+    }
+
+    pub fn connect_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Scans repeated while the sensor is not found
+    pub fn max_retries(&mut self, retries: u32) -> &mut Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Payload format to use instead of detecting it
+    pub fn decoder(&mut self, decoder: Option<DecoderKind>) -> &mut Self {
+        self.decoder = decoder;
+        self
+    }
+
+    /// °C added to every reading
+    pub fn calibration_offset(&mut self, offset: f32) -> &mut Self {
+        self.calibration_offset = offset;
+        self
+    }
+
+    /// Skip peripherals reported weaker than this many dBm
+    pub fn rssi_minimum(&mut self, dbm: Option<i16>) -> &mut Self {
+        self.rssi_minimum = dbm;
+        self
+    }
+
+    /// Characteristic to subscribe to for readings
+    pub fn notify_char_uuid(&mut self, uuid: Uuid) -> &mut Self {
+        self.notify_char_uuid = uuid;
+        self
+    }
+
+    pub fn address_type(&mut self, address_type: AddressType) -> &mut Self {
+        self.address_type = address_type;
+        self
+    }
+
+    /// First and longest wait between reconnection attempts
+    pub fn backoff(&mut self, initial: Duration, max: Duration) -> &mut Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Give up after this many reconnection attempts without connecting,
+    /// `None` to keep trying
+    pub fn max_reconnects(&mut self, max: Option<u32>) -> &mut Self {
+        self.max_reconnects = max;
+        self
+    }
+
+    /// Give up when no sensor is found this long after `run` starts, `None` to
+    /// keep scanning
+    pub fn idle_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Shared with the UI, which measures its latency from it
+    pub fn notify_instant(&mut self, notify_instant: Arc<Mutex<Option<Instant>>>) -> &mut Self {
+        self.notify_instant = notify_instant;
+        self
+    }
+
+    pub fn metrics(&mut self, metrics: Arc<Metrics>) -> &mut Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub fn ble_log(&mut self, log: BleEventLog) -> &mut Self {
+        self.ble_log = Some(log);
+        self
+    }
+
+    /// Every reading is appended here as it arrives, see `--csv-append`
+    pub fn csv_log(&mut self, log: CsvSink<BufWriter<File>>) -> &mut Self {
+        self.csv_log = Some(log);
+        self
+    }
+
+    /// Take readings from `simulated` instead of a Bluetooth sensor
+    pub fn simulate(&mut self, simulated: SimulatedSensor) -> &mut Self {
+        self.simulated = Some(simulated);
+        self
+    }
+
+    /// Fail a `rate` share of the notification decodes at random
+    pub fn inject_errors(&mut self, rate: f64) -> &mut Self {
+        self.inject_errors = Some(rate);
+        self
+    }
+
+    /// A sensor task sending to `bus`. The logs and the simulation move into it, the other
+    /// options stay for building another one
+    pub fn build(&mut self, bus: EventBus) -> TemperatureSendor {
+        let decoder: Box<dyn TemperatureDecoder> = match self.inject_errors {
+            Some(rate) => Box::new(InjectErrors::new(HealthThermometerDecoder, rate)),
+            None => Box::new(HealthThermometerDecoder),
+        };
+        TemperatureSendor {
+            bus,
+            filter: self.device_filter.clone(),
+            notify_instant: self.notify_instant.clone(),
+            metrics: self.metrics.clone(),
+            ble_log: self.ble_log.take(),
+            notify_char: None,
+            notify_char_uuid: self.notify_char_uuid,
+            device_info: None,
+            scan_duration: self.scan_duration,
+            connect_timeout: self.connect_timeout,
+            max_scan_retries: self.max_retries,
+            calibration_offset: self.calibration_offset,
+            rssi_minimum: self.rssi_minimum,
+            initial_backoff: self.initial_backoff,
+            max_backoff: self.max_backoff,
+            max_reconnects: self.max_reconnects,
+            connected: false,
+            idle_timeout: self.idle_timeout,
+            idle_deadline: None,
+            once: None,
+            consecutive_failures: 0,
+            stream_stats: StreamStats::default(),
+            session_id: 0,
+            sensor_name: String::new(),
+            session_decoder: SessionDecoder::new(decoder, 0),
+            decoder: self.decoder,
+            address_type: self.address_type,
+            csv_log: self.csv_log.take(),
+            simulated: self.simulated.take(),
+        }
+    }
+}
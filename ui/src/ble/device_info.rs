@@ -0,0 +1,72 @@
+use btleplug::api::bleuuid::uuid_from_u16;
+use btleplug::api::Peripheral;
+use log::debug;
+use uuid::Uuid;
+
+use crate::error::BleTemperatureError;
+
+const MANUFACTURER_NAME: Uuid = uuid_from_u16(0x2a29);
+const MODEL_NUMBER: Uuid = uuid_from_u16(0x2a24);
+const FIRMWARE_REVISION: Uuid = uuid_from_u16(0x2a26);
+const HARDWARE_REVISION: Uuid = uuid_from_u16(0x2a27);
+
+/// Strings of the Device Information service, `None` if unavailable
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct DeviceInfo {
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub firmware: Option<String>,
+    pub hardware: Option<String>,
+}
+
+impl DeviceInfo {
+    /// `(label, value)` rows for display
+    pub fn fields(&self) -> [(&'static str, &str); 4] {
+        [
+            ("Manufacturer", &self.manufacturer),
+            ("Model", &self.model),
+            ("Firmware", &self.firmware),
+            ("Hardware", &self.hardware),
+        ]
+        .map(|(label, field)| (label, field.as_deref().unwrap_or("N/A")))
+    }
+}
+
+/// Read the Device Information service of a peripheral whose services were
+/// discovered. Missing or unreadable characteristics are left `None`, only
+/// losing the connection is an error
+pub async fn read_device_info<P: Peripheral>(
+    peripheral: &P,
+) -> Result<DeviceInfo, BleTemperatureError> {
+    Ok(DeviceInfo {
+        manufacturer: read_string(peripheral, MANUFACTURER_NAME).await?,
+        model: read_string(peripheral, MODEL_NUMBER).await?,
+        firmware: read_string(peripheral, FIRMWARE_REVISION).await?,
+        hardware: read_string(peripheral, HARDWARE_REVISION).await?,
+    })
+}
+
+async fn read_string<P: Peripheral>(
+    peripheral: &P,
+    uuid: Uuid,
+) -> Result<Option<String>, BleTemperatureError> {
+    let chars = peripheral.characteristics();
+    let Ok(characteristic) = super::find_characteristic(&chars, uuid) else {
+        return Ok(None);
+    };
+
+    match peripheral.read(characteristic).await {
+        Ok(value) => Ok(Some(
+            String::from_utf8_lossy(&value)
+                .trim_end_matches('\0')
+                .to_owned(),
+        )),
+        Err(e @ btleplug::Error::NotConnected) => Err(e.into()),
+        Err(e) => {
+            debug!("failed to read {}: {}", uuid, e);
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! The checks of `--diagnostics`, each a step towards a first reading
+
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+use btleplug::api::{Central, Peripheral as _, ScanFilter};
+use futures::stream::StreamExt;
+
+use super::{first_adapter, TemperatureSendor};
+use crate::decode::DecodeContext;
+
+/// Longest a single check may take
+const STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the scan results are searched for the sensor
+const DISCOVERY_POLL: Duration = Duration::from_millis(500);
+
+#[derive(Clone)]
+pub enum Outcome {
+    Pass,
+    Fail(String),
+    /// An earlier check failed, or the check doesn't apply to the sensor
+    Skipped,
+}
+
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    /// What to try when the check fails
+    pub remedy: &'static str,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        !matches!(self.outcome, Outcome::Fail(_))
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.outcome {
+            Outcome::Pass => write!(f, "[PASS] {}", self.name),
+            Outcome::Skipped => write!(f, "[SKIP] {}", self.name),
+            Outcome::Fail(e) => write!(f, "[FAIL] {}: {}\n       {}", self.name, e, self.remedy),
+        }
+    }
+}
+
+/// Names and remedies of the checks, in the order they run
+const CHECKS: [(&str, &str); 6] = [
+    (
+        "Bluetooth adapter present",
+        "plug in a Bluetooth adapter, and check that `rfkill list` doesn't block it",
+    ),
+    (
+        "Adapter powered on",
+        "power the adapter on, e.g. with `bluetoothctl power on`",
+    ),
+    (
+        "Target sensor discoverable",
+        "check that the sensor is on and in range, and that --filter matches its name",
+    ),
+    (
+        "Can connect to sensor",
+        "move the sensor closer, or forget a stale pairing with `bluetoothctl remove <address>`",
+    ),
+    (
+        "Can receive notification",
+        "check that the sensor is measuring, and --characteristic if it uses another one",
+    ),
+    (
+        "Decode succeeds on first packet",
+        "the sensor sends another format, try --decoder or --characteristic",
+    ),
+];
+
+/// `step`, failing once it runs over `STEP_TIMEOUT`
+async fn timed<T, E: fmt::Display>(step: impl Future<Output = Result<T, E>>) -> Result<T, String> {
+    match tokio::time::timeout(STEP_TIMEOUT, step).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("timed out after {}s", STEP_TIMEOUT.as_secs())),
+    }
+}
+
+impl TemperatureSendor {
+    /// Run the checks in order until one fails, skipping the rest
+    pub async fn diagnose(&mut self) -> Vec<Check> {
+        let mut outcomes = Vec::new();
+        // the first failure ends the run, `Err(())` as it's already recorded
+        let _ = self.run_checks(&mut outcomes).await;
+
+        CHECKS
+            .iter()
+            .enumerate()
+            .map(|(i, &(name, remedy))| Check {
+                name,
+                outcome: outcomes.get(i).cloned().unwrap_or(Outcome::Skipped),
+                remedy,
+            })
+            .collect()
+    }
+
+    async fn run_checks(&mut self, outcomes: &mut Vec<Outcome>) -> Result<(), ()> {
+        fn record<T>(outcomes: &mut Vec<Outcome>, result: Result<T, String>) -> Result<T, ()> {
+            match result {
+                Ok(value) => {
+                    outcomes.push(Outcome::Pass);
+                    Ok(value)
+                }
+                Err(e) => {
+                    outcomes.push(Outcome::Fail(e));
+                    Err(())
+                }
+            }
+        }
+
+        let central = record(outcomes, timed(first_adapter()).await)?;
+        record(
+            outcomes,
+            timed(central.start_scan(ScanFilter::default())).await,
+        )?;
+
+        let discovered = timed(async {
+            loop {
+                tokio::time::sleep(DISCOVERY_POLL).await;
+                if let Ok(found) = self.find_sensor(&central).await {
+                    return Ok::<_, String>(found);
+                }
+            }
+        })
+        .await;
+        let _ = central.stop_scan().await;
+        let (sensor, kind) = record(outcomes, discovered)?;
+        if kind.advertised_data().is_some() {
+            // read from advertisements, there is nothing to connect to
+            return Ok(());
+        }
+
+        record(outcomes, timed(sensor.connect()).await)?;
+        let received = timed(async {
+            let notify_char = self.discover_and_subscribe(&sensor).await?;
+            let first = sensor.notifications().await?.next().await;
+            let _ = sensor.unsubscribe(&notify_char).await;
+            first.ok_or_else(|| Box::<dyn Error>::from("notifications ended"))
+        })
+        .await;
+        let _ = sensor.disconnect().await;
+        let notification = record(outcomes, received)?;
+
+        self.session_decoder.reset(self.session_id);
+        let context = DecodeContext {
+            session_id: self.session_id,
+        };
+        let decoded = self
+            .session_decoder
+            .decode(&notification.value, context)
+            .ok_or_else(|| format!("undecodable payload {:02x?}", notification.value));
+        record(outcomes, decoded)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use log::warn;
+use tokio::sync::broadcast::{self, error::RecvError, error::SendError};
+
+use super::Event;
+use crate::metrics::Metrics;
+
+/// Events of the sensor task, delivered to every subscriber
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    /// Subscribers falling more than `capacity` events behind miss the oldest ones
+    pub fn new(capacity: usize) -> Self {
+        let (tx, _) = broadcast::channel(capacity);
+        Self { tx }
+    }
+
+    /// Fails once there are no subscribers left
+    pub fn send(&self, event: Event) -> Result<(), SendError<Event>> {
+        self.tx.send(event).map(|_| ())
+    }
+
+    /// A receiver of the events sent from now on
+    pub fn subscriber(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+}
+
+/// Pass the events of `rx` on to `tx`, the channel the UI reads, until the bus
+/// closes or the UI goes away. Dropping `rx` then lets the sensor task notice
+/// when the UI was its only subscriber
+pub async fn forward(mut rx: broadcast::Receiver<Event>, tx: Sender<Event>, metrics: Arc<Metrics>) {
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                warn!("UI fell behind, {} events dropped", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        // count before sending so the UI never decrements below zero
+        metrics.queue_depth.fetch_add(1, Ordering::Relaxed);
+        if tx.send(event).is_err() {
+            metrics.queue_depth.fetch_sub(1, Ordering::Relaxed);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::TemperatureMeasurement;
+
+    #[test]
+    fn every_subscriber_gets_each_event() {
+        let bus = EventBus::new(4);
+        let (mut first, mut second) = (bus.subscriber(), bus.subscriber());
+        bus.send(Event::Reading(TemperatureMeasurement::from(21.5)))
+            .unwrap();
+        assert!(matches!(first.try_recv(), Ok(Event::Reading(r)) if r.celsius == 21.5));
+        assert!(matches!(second.try_recv(), Ok(Event::Reading(r)) if r.celsius == 21.5));
+    }
+
+    #[test]
+    fn sending_fails_without_subscribers() {
+        let bus = EventBus::new(4);
+        assert!(bus
+            .send(Event::Reading(TemperatureMeasurement::from(21.5)))
+            .is_err());
+        drop(bus.subscriber());
+        assert!(bus
+            .send(Event::Reading(TemperatureMeasurement::from(21.5)))
+            .is_err());
+    }
+
+    #[test]
+    fn forwarding_ends_with_the_bus() {
+        let bus = EventBus::new(4);
+        let (tx, rx) = std::sync::mpsc::channel();
+        let metrics = Arc::new(Metrics::default());
+        let forwarder = forward(bus.subscriber(), tx, metrics.clone());
+        bus.send(Event::Reading(TemperatureMeasurement::from(21.5)))
+            .unwrap();
+        drop(bus);
+
+        tokio::runtime::Runtime::new().unwrap().block_on(forwarder);
+        assert!(matches!(rx.try_recv(), Ok(Event::Reading(r)) if r.celsius == 21.5));
+        assert!(rx.try_recv().is_err());
+        assert_eq!(metrics.queue_depth.load(Ordering::Relaxed), 1);
+    }
+}
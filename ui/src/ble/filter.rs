@@ -0,0 +1,84 @@
+use btleplug::api::bleuuid::uuid_from_u16;
+use btleplug::api::{BDAddr, PeripheralProperties};
+use regex::Regex;
+use uuid::Uuid;
+
+/// Which scanned peripherals are taken for the sensor
+#[derive(Clone, Debug)]
+pub enum DeviceFilter {
+    /// Advertised local name matches
+    ByName(Regex),
+    ByAddress(BDAddr),
+    /// The service is in the advertisement, for devices without a fixed name
+    ByServiceUuid(Uuid),
+    /// Any of the filters matches
+    Any(Vec<DeviceFilter>),
+}
+
+impl DeviceFilter {
+    pub fn matches(&self, properties: &PeripheralProperties) -> bool {
+        match self {
+            DeviceFilter::ByName(name) => properties
+                .local_name
+                .as_ref()
+                .is_some_and(|local_name| name.is_match(local_name)),
+            DeviceFilter::ByAddress(address) => properties.address == *address,
+            DeviceFilter::ByServiceUuid(uuid) => properties.services.contains(uuid),
+            DeviceFilter::Any(filters) => filters.iter().any(|f| f.matches(properties)),
+        }
+    }
+}
+
+/// A 16-bit assigned number like `0x1809` or `1809`, or a full UUID
+pub fn parse_uuid(s: &str) -> Result<Uuid, String> {
+    let short = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X"));
+    match short {
+        Some(hex) => u16::from_str_radix(hex, 16).map(uuid_from_u16),
+        None if s.len() <= 4 => u16::from_str_radix(s, 16).map(uuid_from_u16),
+        None => return Uuid::parse_str(s).map_err(|e| e.to_string()),
+    }
+    .map_err(|e| format!("invalid 16-bit UUID: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advertising(name: Option<&str>, services: Vec<Uuid>) -> PeripheralProperties {
+        PeripheralProperties {
+            local_name: name.map(str::to_owned),
+            services,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn any_matches_name_or_service() {
+        let thermometer = uuid_from_u16(0x1809);
+        let filter = DeviceFilter::Any(vec![
+            DeviceFilter::ByName(Regex::new("(?i)temperature").unwrap()),
+            DeviceFilter::ByServiceUuid(thermometer),
+        ]);
+
+        assert!(filter.matches(&advertising(Some("Temperature01"), vec![])));
+        assert!(filter.matches(&advertising(None, vec![thermometer])));
+        assert!(!filter.matches(&advertising(
+            Some("Heart Rate"),
+            vec![uuid_from_u16(0x180d)]
+        )));
+        assert!(!DeviceFilter::Any(vec![]).matches(&advertising(Some("Temperature01"), vec![])));
+    }
+
+    #[test]
+    fn parses_short_and_full_uuids() {
+        let thermometer = uuid_from_u16(0x1809);
+        assert_eq!(parse_uuid("0x1809"), Ok(thermometer));
+        assert_eq!(parse_uuid("1809"), Ok(thermometer));
+        assert_eq!(
+            parse_uuid("00001809-0000-1000-8000-00805f9b34fb"),
+            Ok(thermometer)
+        );
+        assert!(parse_uuid("0x18090").is_err());
+        assert!(parse_uuid("thermometer").is_err());
+    }
+}
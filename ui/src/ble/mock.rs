@@ -0,0 +1,263 @@
+//! In-memory btleplug manager, adapter and peripheral, for running the scan,
+//! connect and subscribe flow without Bluetooth hardware
+
+use std::collections::BTreeSet;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use btleplug::api::{
+    BDAddr, Central, CentralEvent, CharPropFlags, Characteristic, Descriptor, Manager, Peripheral,
+    PeripheralProperties, ScanFilter, Service, ValueNotification, WriteType,
+};
+use btleplug::platform::PeripheralId;
+use btleplug::{Error, Result};
+use futures::stream::{self, Stream, StreamExt};
+use uuid::Uuid;
+
+use crate::decode::TEMPERATURE_MEASUREMENT;
+
+/// Health Thermometer service
+const HEALTH_THERMOMETER: Uuid = btleplug::api::bleuuid::uuid_from_u16(0x1809);
+
+/// What btleplug would call the peripheral at `address`. Each backend has its
+/// own `PeripheralId`, and the BlueZ one has no public constructor, so it is
+/// built from its serialized form, the D-Bus object path of the device
+#[cfg(target_os = "linux")]
+fn peripheral_id(address: BDAddr) -> PeripheralId {
+    let device = address.to_string().replace(':', "_");
+    let id = serde_json::json!({ "object_path": format!("/org/bluez/hci0/dev_{}", device) });
+    serde_json::from_value(id).expect("BlueZ peripheral id of a mock address")
+}
+
+/// CoreBluetooth hides addresses behind a UUID per peripheral, this one ends
+/// in the address
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn peripheral_id(address: BDAddr) -> PeripheralId {
+    let mut bytes = [0; 16];
+    bytes[10..].copy_from_slice(&address.into_inner());
+    PeripheralId::from(Uuid::from_bytes(bytes))
+}
+
+#[cfg(target_os = "windows")]
+fn peripheral_id(address: BDAddr) -> PeripheralId {
+    PeripheralId::from(address)
+}
+
+/// Android ids are the address, without a public constructor either
+#[cfg(target_os = "android")]
+fn peripheral_id(address: BDAddr) -> PeripheralId {
+    serde_json::from_value(serde_json::json!(address.to_string()))
+        .expect("Android peripheral id of a mock address")
+}
+
+/// A sensor that accepts every connection and delivers canned notifications
+/// of the temperature measurement characteristic
+#[derive(Clone, Debug)]
+pub struct MockPeripheral {
+    pub properties: PeripheralProperties,
+    pub services: BTreeSet<Service>,
+    /// Values delivered by `notifications()`, one stream per call
+    pub notifications: Vec<Vec<u8>>,
+    connected: Arc<AtomicBool>,
+}
+
+impl MockPeripheral {
+    /// A Health Thermometer advertising `name`
+    pub fn thermometer(address: BDAddr, name: &str, notifications: Vec<Vec<u8>>) -> Self {
+        let measurement = Characteristic {
+            uuid: TEMPERATURE_MEASUREMENT,
+            service_uuid: HEALTH_THERMOMETER,
+            properties: CharPropFlags::INDICATE,
+            descriptors: BTreeSet::new(),
+        };
+        Self {
+            properties: PeripheralProperties {
+                address,
+                local_name: Some(name.to_owned()),
+                services: vec![HEALTH_THERMOMETER],
+                ..Default::default()
+            },
+            services: BTreeSet::from([Service {
+                uuid: HEALTH_THERMOMETER,
+                primary: true,
+                characteristics: BTreeSet::from([measurement]),
+            }]),
+            notifications,
+            connected: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+#[async_trait]
+impl Peripheral for MockPeripheral {
+    fn id(&self) -> PeripheralId {
+        peripheral_id(self.properties.address)
+    }
+
+    fn address(&self) -> BDAddr {
+        self.properties.address
+    }
+
+    async fn properties(&self) -> Result<Option<PeripheralProperties>> {
+        Ok(Some(self.properties.clone()))
+    }
+
+    fn services(&self) -> BTreeSet<Service> {
+        self.services.clone()
+    }
+
+    async fn is_connected(&self) -> Result<bool> {
+        Ok(self.connected.load(Ordering::Relaxed))
+    }
+
+    async fn connect(&self) -> Result<()> {
+        self.connected.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<()> {
+        self.connected.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn discover_services(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn write(&self, _: &Characteristic, _: &[u8], _: WriteType) -> Result<()> {
+        Ok(())
+    }
+
+    async fn read(&self, characteristic: &Characteristic) -> Result<Vec<u8>> {
+        Err(Error::NotSupported(format!(
+            "mock read of {}",
+            characteristic.uuid
+        )))
+    }
+
+    async fn subscribe(&self, _: &Characteristic) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe(&self, _: &Characteristic) -> Result<()> {
+        Ok(())
+    }
+
+    /// Ends once the canned values ran out, disconnecting like a sensor that
+    /// went out of range
+    async fn notifications(&self) -> Result<Pin<Box<dyn Stream<Item = ValueNotification> + Send>>> {
+        let values = self.notifications.clone().into_iter().map(|value| {
+            Some(ValueNotification {
+                uuid: TEMPERATURE_MEASUREMENT,
+                value,
+            })
+        });
+        let connected = self.connected.clone();
+        let dropped = stream::once(async move {
+            connected.store(false, Ordering::Relaxed);
+            None
+        });
+        Ok(Box::pin(
+            stream::iter(values)
+                .chain(dropped)
+                .filter_map(|notification| async move { notification }),
+        ))
+    }
+
+    async fn write_descriptor(&self, _: &Descriptor, _: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    async fn read_descriptor(&self, descriptor: &Descriptor) -> Result<Vec<u8>> {
+        Err(Error::NotSupported(format!(
+            "mock read of {}",
+            descriptor.uuid
+        )))
+    }
+}
+
+/// An adapter whose scans find a fixed set of peripherals
+#[derive(Clone, Debug, Default)]
+pub struct MockAdapter {
+    pub peripherals: Vec<MockPeripheral>,
+    scanning: Arc<AtomicBool>,
+}
+
+impl MockAdapter {
+    pub fn new(peripherals: Vec<MockPeripheral>) -> Self {
+        Self {
+            peripherals,
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait]
+impl Central for MockAdapter {
+    type Peripheral = MockPeripheral;
+
+    async fn events(&self) -> Result<Pin<Box<dyn Stream<Item = CentralEvent> + Send>>> {
+        let ids: Vec<PeripheralId> = self.peripherals.iter().map(|p| p.id()).collect();
+        Ok(Box::pin(stream::iter(
+            ids.into_iter().map(CentralEvent::DeviceDiscovered),
+        )))
+    }
+
+    async fn start_scan(&self, _: ScanFilter) -> Result<()> {
+        self.scanning.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn stop_scan(&self) -> Result<()> {
+        self.scanning.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Nothing is known before the first scan, like on a fresh adapter
+    async fn peripherals(&self) -> Result<Vec<MockPeripheral>> {
+        if !self.scanning.load(Ordering::Relaxed) {
+            return Ok(Vec::new());
+        }
+        Ok(self.peripherals.clone())
+    }
+
+    async fn peripheral(&self, id: &PeripheralId) -> Result<MockPeripheral> {
+        self.peripherals
+            .iter()
+            .find(|p| p.id() == *id)
+            .cloned()
+            .ok_or(Error::DeviceNotFound)
+    }
+
+    async fn add_peripheral(&self, _: &PeripheralId) -> Result<MockPeripheral> {
+        Err(Error::NotSupported(
+            "mock adapters only know their peripherals".into(),
+        ))
+    }
+
+    async fn adapter_info(&self) -> Result<String> {
+        Ok("mock adapter".into())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MockManager {
+    pub adapters: Vec<MockAdapter>,
+}
+
+impl MockManager {
+    pub fn new(adapters: Vec<MockAdapter>) -> Self {
+        Self { adapters }
+    }
+}
+
+#[async_trait]
+impl Manager for MockManager {
+    type Adapter = MockAdapter;
+
+    async fn adapters(&self) -> Result<Vec<MockAdapter>> {
+        Ok(self.adapters.clone())
+    }
+}
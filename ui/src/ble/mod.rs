@@ -0,0 +1,1251 @@
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use btleplug::api::{
+    BDAddr, CentralEvent, Characteristic, Manager as _, Peripheral as _, PeripheralProperties,
+    ValueNotification,
+};
+use btleplug::{
+    api::{Central, ScanFilter},
+    platform::{Adapter, Manager},
+};
+
+use eframe::egui;
+use egui::Context;
+
+use futures::stream::{BoxStream, StreamExt};
+use log::{debug, error, info, warn};
+use tokio::sync::broadcast::error::SendError;
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+mod addr;
+mod builder;
+pub mod device_info;
+pub mod diagnostics;
+mod event_bus;
+mod filter;
+#[cfg(all(test, feature = "mock-ble"))]
+mod mock;
+mod scan;
+mod state;
+mod stream_stats;
+
+use crate::ble_log::BleEventLog;
+use crate::decode::{
+    AdvertisedData, DecodeContext, DecoderKind, SessionDecoder, TemperatureDecoder,
+    TemperatureMeasurement,
+};
+use crate::error::BleTemperatureError;
+use crate::event_log::LogEvent;
+use crate::metrics::Metrics;
+use crate::simulate::SimulatedSensor;
+use crate::sink::{CsvSink, OutputSink};
+pub use addr::BdAddrExt;
+pub use builder::TemperatureSendorBuilder;
+use device_info::{read_device_info, DeviceInfo};
+pub use event_bus::{forward, EventBus};
+pub use filter::{parse_uuid, DeviceFilter};
+pub use scan::{scan_devices, ScanResult};
+pub use state::ConnectionState;
+pub use stream_stats::StreamStats;
+
+/// Messages from the sensor task to the UI and other subscribers
+#[derive(Clone, Debug)]
+pub enum Event {
+    Connected {
+        name: String,
+        address: String,
+    },
+    Reading(TemperatureMeasurement),
+    DeviceInfo(DeviceInfo),
+    /// Signal strength in dBm
+    Rssi(i16),
+    /// Lifecycle events seen only by the sensor task
+    Log(LogEvent),
+    State(ConnectionState),
+    /// Sent every `STREAM_STATS_INTERVAL` while notifications stream
+    StreamStats(StreamStats),
+    /// The sensor task stopped and waits for a retry
+    Error(String),
+}
+
+/// How often the stream stats are sent to the UI
+const STREAM_STATS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What `TemperatureSendor::handle_payload` did with a payload
+enum Handled {
+    /// Sent on as a reading
+    Reading,
+    /// Undecodable
+    Dropped,
+    /// Taken by `once`, which ends the session
+    Taken,
+}
+
+/// Where a payload handed to `TemperatureSendor::handle_payload` came from
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    /// A notification of the session in the context
+    Notification(DecodeContext),
+    /// An advertisement, read by the decoder of its advertiser
+    Advertisement(&'a dyn TemperatureDecoder),
+}
+
+/// btleplug has no connect-time address type hint, so the type narrows down
+/// which scanned peripherals are considered instead
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum AddressType {
+    Public,
+    Random,
+    /// Whatever the scan reported
+    Auto,
+}
+
+impl AddressType {
+    /// Unknown types are accepted, not every platform reports them
+    pub fn matches(self, reported: Option<btleplug::api::AddressType>) -> bool {
+        use btleplug::api::AddressType as Reported;
+
+        match (self, reported) {
+            (AddressType::Auto, _) | (_, None) => true,
+            (AddressType::Public, Some(reported)) => reported == Reported::Public,
+            (AddressType::Random, Some(reported)) => reported == Reported::Random,
+        }
+    }
+}
+
+pub struct TemperatureSendor {
+    bus: EventBus,
+    filter: DeviceFilter,
+    /// When the last notification arrived, for measuring UI latency
+    notify_instant: Arc<Mutex<Option<Instant>>>,
+    metrics: Arc<Metrics>,
+    ble_log: Option<BleEventLog>,
+    /// Temperature characteristic from the last discovery, reused on reconnect
+    notify_char: Option<Characteristic>,
+    notify_char_uuid: Uuid,
+    /// Read along with the characteristic discovery
+    device_info: Option<DeviceInfo>,
+    /// How long each scan runs before looking for the sensor
+    scan_duration: Duration,
+    connect_timeout: Duration,
+    /// Scans repeated when the sensor was not found
+    max_scan_retries: u32,
+    /// °C added to every reading
+    calibration_offset: f32,
+    /// Weaker peripherals are skipped, in dBm
+    rssi_minimum: Option<i16>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    /// Reconnection attempts before `run` gives up
+    max_reconnects: Option<u32>,
+    /// Whether the current session got connected, which starts the attempt
+    /// count over
+    connected: bool,
+    idle_timeout: Option<Duration>,
+    /// End of the `idle_timeout`, until the sensor is first found
+    idle_deadline: Option<Instant>,
+    /// Takes the first reading instead of the UI, ending the session
+    pub once: Option<oneshot::Sender<TemperatureMeasurement>>,
+    consecutive_failures: u32,
+    stream_stats: StreamStats,
+    /// Counts connection sessions, for telling apart their notifications
+    session_id: u64,
+    /// `peripheral_name` of the sensor last connected to
+    sensor_name: String,
+    session_decoder: SessionDecoder<Box<dyn TemperatureDecoder>>,
+    /// Payload format to use instead of detecting it
+    decoder: Option<DecoderKind>,
+    address_type: AddressType,
+    /// Every reading is appended here as it arrives, see `--csv-append`
+    csv_log: Option<CsvSink<BufWriter<File>>>,
+    /// Replaces the Bluetooth sensor, see `--simulate`
+    simulated: Option<SimulatedSensor>,
+}
+
+impl TemperatureSendor {
+    fn log_ble(&self, event: &str) {
+        if let Some(log) = &self.ble_log {
+            log.event(event);
+        }
+    }
+
+    /// Fails once nothing subscribes to the bus any more
+    pub fn send(&self, event: Event) -> Result<(), SendError<Event>> {
+        self.bus.send(event)
+    }
+
+    /// Notifications counted since the task was built, over every session
+    pub fn notification_stream_stats(&self) -> StreamStats {
+        self.stream_stats
+    }
+
+    pub fn is_simulated(&self) -> bool {
+        self.simulated.is_some()
+    }
+
+    /// Forward readings, reconnecting as needed, until the UI goes away or
+    /// `cancel` is cancelled
+    pub async fn run(
+        &mut self,
+        egui_ctx: &Context,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(simulated) = self.simulated.take() {
+            return match self.simulate(simulated, egui_ctx, cancel).await {
+                Err(e) if e.is::<SendError<Event>>() => {
+                    info!("UI channel closed, sensor task shutting down");
+                    Ok(())
+                }
+                result => result,
+            };
+        }
+
+        let central = first_adapter().await?;
+        self.run_on(&central, egui_ctx, cancel).await
+    }
+
+    /// `run` against `central` instead of the first platform adapter
+    pub async fn run_on<C: Central>(
+        &mut self,
+        central: &C,
+        egui_ctx: &Context,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn Error>> {
+        self.idle_deadline = self.idle_timeout.map(|timeout| Instant::now() + timeout);
+
+        let mut cache = BleCache::default();
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 0;
+        loop {
+            let notifications = self.metrics.notifications.load(Ordering::Relaxed);
+            self.connected = false;
+            match self
+                .cached_session(central, &mut cache, egui_ctx, cancel)
+                .await
+            {
+                Err(e) if e.is::<SendError<Event>>() => {
+                    info!("UI channel closed, sensor task shutting down");
+                    return Ok(());
+                }
+                // retrying cannot help with these
+                Err(e)
+                    if matches!(
+                        e.downcast_ref(),
+                        Some(
+                            BleTemperatureError::IdleTimeout(_)
+                                | BleTemperatureError::PermissionDenied
+                        )
+                    ) =>
+                {
+                    return Err(e);
+                }
+                Err(e) => warn!("sensor session ended: {}", e),
+                Ok(()) => {}
+            }
+            if cancel.is_cancelled() {
+                info!("sensor task cancelled");
+                return Ok(());
+            }
+
+            // a session that delivered data starts the schedule over
+            if self.metrics.notifications.load(Ordering::Relaxed) != notifications {
+                backoff = self.initial_backoff;
+            }
+            if self.connected {
+                attempt = 0;
+            }
+
+            attempt += 1;
+            if let Some(max) = self.max_reconnects.filter(|max| attempt > *max) {
+                error!("giving up after {} reconnect attempts", max);
+                return Err(BleTemperatureError::TooManyReconnects(max).into());
+            }
+            let next_retry = Instant::now() + backoff;
+            if self
+                .send(Event::State(ConnectionState::Reconnecting {
+                    attempt,
+                    max_attempts: self.max_reconnects,
+                    next_retry,
+                }))
+                .is_err()
+            {
+                info!("UI channel closed, sensor task shutting down");
+                return Ok(());
+            }
+
+            match self.max_reconnects {
+                Some(max) => warn!(
+                    "reconnect attempt {}/{} in {}s",
+                    attempt,
+                    max,
+                    backoff.as_secs()
+                ),
+                None => warn!("reconnect attempt {} in {}s", attempt, backoff.as_secs()),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = cancel.cancelled() => {
+                    info!("sensor task cancelled");
+                    return Ok(());
+                }
+            }
+            backoff = (backoff * 2).min(self.max_backoff);
+
+            if self
+                .send(Event::Log(LogEvent::ReconnectAttempt { n: attempt }))
+                .is_err()
+            {
+                info!("UI channel closed, sensor task shutting down");
+                return Ok(());
+            }
+        }
+    }
+
+    /// Scan, connect and forward readings until the sensor goes away or
+    /// `cancel` is cancelled. Known sensors are cached per `Central`, so a
+    /// single session starts without any; `run_on` keeps them across
+    /// reconnects
+    pub async fn session<C: Central>(
+        &mut self,
+        central: &C,
+        egui_ctx: &Context,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn Error>> {
+        self.cached_session(central, &mut BleCache::default(), egui_ctx, cancel)
+            .await
+    }
+
+    /// `session`, connecting to a sensor of `cache` without scanning if possible
+    async fn cached_session<C: Central>(
+        &mut self,
+        central: &C,
+        cache: &mut BleCache<C::Peripheral>,
+        egui_ctx: &Context,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn Error>> {
+        let sensor = match cache.connect(self.connect_timeout, &self.bus).await? {
+            Some(sensor) => sensor,
+            None => {
+                // start scanning for devices
+                self.log_ble("scan");
+                self.send(Event::Log(LogEvent::ScanStarted))?;
+                self.send(Event::State(ConnectionState::Scanning))?;
+                let (sensor, kind) = self.scan_before_idle_timeout(central).await?;
+                if let Some(advertised) = kind.advertised_data() {
+                    return self
+                        .watch_advertisements(central, &sensor, advertised, kind, egui_ctx, cancel)
+                        .await;
+                }
+
+                info!("connecting to sensor: {}", sensor.address().to_colon_hex());
+                self.send(Event::State(ConnectionState::Connecting {
+                    address: sensor.address().to_colon_hex(),
+                }))?;
+                tokio::time::timeout(self.connect_timeout, sensor.connect())
+                    .await
+                    .map_err(|_| BleTemperatureError::ConnectTimeout)??;
+                cache
+                    .known_peripherals
+                    .insert(sensor.address(), sensor.clone());
+                sensor
+            }
+        };
+        self.log_ble(&format!("connect {}", sensor.address().to_colon_hex()));
+        let _connection = ConnectionGuard::new(&self.metrics);
+        self.connected = true;
+
+        self.sensor_name = peripheral_name(&sensor).await;
+        info!("connected to {}", self.sensor_name);
+        self.send(Event::Connected {
+            name: self.sensor_name.clone(),
+            address: sensor.address().to_colon_hex(),
+        })?;
+
+        let notify_char = match self.notify_char.clone() {
+            Some(notify_char) => {
+                info!("subscribing to cached characteristic");
+                match sensor.subscribe(&notify_char).await {
+                    Ok(()) => notify_char,
+                    Err(e) => {
+                        warn!("cached characteristic failed ({}), rediscovering", e);
+                        self.notify_char = None;
+                        self.discover_and_subscribe(&sensor).await?
+                    }
+                }
+            }
+            None => self.discover_and_subscribe(&sensor).await?,
+        };
+        self.log_ble(&format!("subscribe {}", notify_char.uuid));
+
+        if let Some(info) = &self.device_info {
+            self.send(Event::DeviceInfo(info.clone()))?;
+        }
+
+        let mut stream = self.session_notifications(&sensor).await?;
+
+        let mut stats_ticks = stream_stats_interval();
+        loop {
+            let (context, data) = tokio::select! {
+                data = stream.next() => match data {
+                    Some(data) => data,
+                    None => break,
+                },
+                _ = stats_ticks.tick() => {
+                    self.send(Event::StreamStats(self.notification_stream_stats()))?;
+                    continue;
+                }
+                _ = cancel.cancelled() => {
+                    sensor.disconnect().await?;
+                    break;
+                }
+            };
+            if let Some(log) = &self.ble_log {
+                log.notification(&data);
+            }
+            match self.handle_payload(&data.value, Source::Notification(context))? {
+                Handled::Reading => {
+                    self.send_rssi(&sensor).await?;
+                    egui_ctx.request_repaint()
+                }
+                Handled::Dropped => {}
+                Handled::Taken => {
+                    sensor.disconnect().await?;
+                    break;
+                }
+            }
+        }
+        info!("disconnected from {}", self.sensor_name);
+        self.log_ble(&format!("disconnect {}", sensor.address().to_colon_hex()));
+        self.send(Event::Log(LogEvent::Disconnected))?;
+
+        Ok(())
+    }
+
+    /// Decode and forward the notifications of `simulated` like those of a
+    /// connected sensor, until the UI goes away
+    async fn simulate(
+        &mut self,
+        mut simulated: SimulatedSensor,
+        egui_ctx: &Context,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn Error>> {
+        info!("simulating a sensor");
+        let _connection = ConnectionGuard::new(&self.metrics);
+        self.sensor_name = "Simulated".into();
+        self.send(Event::Connected {
+            name: self.sensor_name.clone(),
+            address: BDAddr::default().to_colon_hex(),
+        })?;
+
+        let context = self.new_session();
+
+        let start = Instant::now();
+        let mut ticks = tokio::time::interval(simulated.interval);
+        let mut stats_ticks = stream_stats_interval();
+        loop {
+            tokio::select! {
+                _ = ticks.tick() => {}
+                _ = stats_ticks.tick() => {
+                    self.send(Event::StreamStats(self.notification_stream_stats()))?;
+                    continue;
+                }
+                _ = cancel.cancelled() => return Ok(()),
+            }
+            let data = simulated.notification(start.elapsed());
+            match self.handle_payload(&data, Source::Notification(context))? {
+                Handled::Reading => egui_ctx.request_repaint(),
+                Handled::Dropped => {}
+                Handled::Taken => return Ok(()),
+            }
+        }
+    }
+
+    /// Start a new session, whose notifications the decoder accepts from now on
+    fn new_session(&mut self) -> DecodeContext {
+        self.session_id += 1;
+        self.session_decoder.reset(self.session_id);
+        DecodeContext {
+            session_id: self.session_id,
+        }
+    }
+
+    /// Start a new session and subscribe to the notifications of `sensor`,
+    /// each tagged with that session, so the decoder rejects any that are
+    /// read after a later session started
+    async fn session_notifications<P: btleplug::api::Peripheral>(
+        &mut self,
+        sensor: &P,
+    ) -> btleplug::Result<BoxStream<'static, (DecodeContext, ValueNotification)>> {
+        let context = self.new_session();
+        let stream = sensor.notifications().await?;
+        Ok(stream.map(move |data| (context, data)).boxed())
+    }
+
+    /// Decode one payload of `source` and pass the reading on to `once` or
+    /// else the CSV log and the bus, counting it in the metrics
+    fn handle_payload(
+        &mut self,
+        data: &[u8],
+        source: Source<'_>,
+    ) -> Result<Handled, SendError<Event>> {
+        *self.notify_instant.lock().unwrap() = Some(Instant::now());
+        self.metrics.notifications.fetch_add(1, Ordering::Relaxed);
+        self.metrics
+            .bytes_received
+            .fetch_add(data.len() as u64, Ordering::Relaxed);
+
+        let decoded = match source {
+            Source::Advertisement(decoder) => match decoder.decode(data) {
+                Some(celsius) => Some(TemperatureMeasurement::from(celsius)),
+                // beacons interleave other frame types, those are not failures
+                None => return Ok(Handled::Dropped),
+            },
+            Source::Notification(context) => {
+                let decoded = self.session_decoder.decode(data, context);
+                self.stream_stats.record(data, decoded.is_some());
+                decoded
+            }
+        };
+        let Some(measurement) = decoded else {
+            self.metrics.decode_failures.fetch_add(1, Ordering::Relaxed);
+            self.send(Event::Log(LogEvent::DecodeFailed { raw: data.to_vec() }))?;
+            self.consecutive_failures += 1;
+            if self.consecutive_failures.is_multiple_of(10) {
+                warn!(
+                    "{} consecutive decode failures; check decoder format",
+                    self.consecutive_failures
+                );
+            }
+            return Ok(Handled::Dropped);
+        };
+
+        self.metrics.decoded.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_failures = 0;
+        let measurement = TemperatureMeasurement {
+            celsius: measurement.celsius + self.calibration_offset,
+            ..measurement
+        };
+        if let Some(once) = self.once.take() {
+            let _ = once.send(measurement);
+            return Ok(Handled::Taken);
+        }
+        *self.metrics.readings.lock().unwrap() += measurement;
+        append_csv(&mut self.csv_log, &measurement);
+        self.send(Event::Reading(measurement))?;
+        Ok(Handled::Reading)
+    }
+
+    /// Full GATT discovery, caching the temperature characteristic for reconnects
+    async fn discover_and_subscribe<P: btleplug::api::Peripheral>(
+        &mut self,
+        sensor: &P,
+    ) -> Result<Characteristic, BleTemperatureError> {
+        info!("discovering services");
+        sensor.discover_services().await?;
+
+        info!("findind temperature characteristic");
+        let chars = sensor.characteristics();
+        let notify_char = find_characteristic(&chars, self.notify_char_uuid)?;
+
+        info!("subscribing to characteristic");
+        sensor.subscribe(notify_char).await?;
+
+        self.notify_char = Some(notify_char.clone());
+        self.device_info = Some(read_device_info(sensor).await?);
+        Ok(notify_char.clone())
+    }
+
+    /// Forward the signal strength, if the platform reports it
+    async fn send_rssi<P: btleplug::api::Peripheral>(
+        &self,
+        sensor: &P,
+    ) -> Result<(), SendError<Event>> {
+        match sensor.properties().await {
+            Ok(Some(PeripheralProperties {
+                rssi: Some(rssi), ..
+            })) => self.send(Event::Rssi(rssi)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Readings broadcast in advertisements, no connection needed
+    async fn watch_advertisements<C: Central>(
+        &mut self,
+        central: &C,
+        sensor: &C::Peripheral,
+        advertised: AdvertisedData,
+        kind: DecoderKind,
+        egui_ctx: &Context,
+        cancel: &CancellationToken,
+    ) -> Result<(), Box<dyn Error>> {
+        self.connected = true;
+        self.sensor_name = peripheral_name(sensor).await;
+        info!("reading {:?} advertisements of {}", kind, self.sensor_name);
+        self.send(Event::Connected {
+            name: self.sensor_name.clone(),
+            address: sensor.address().to_colon_hex(),
+        })?;
+
+        let decoder = kind.decoder();
+        let mut events = central.events().await?;
+        loop {
+            let event = tokio::select! {
+                event = events.next() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+                _ = cancel.cancelled() => break,
+            };
+            let (id, data) = match (event, advertised) {
+                (
+                    CentralEvent::ServiceDataAdvertisement {
+                        id,
+                        mut service_data,
+                    },
+                    AdvertisedData::Service(uuid),
+                ) => (id, service_data.remove(&uuid)),
+                (
+                    CentralEvent::ManufacturerDataAdvertisement {
+                        id,
+                        mut manufacturer_data,
+                    },
+                    AdvertisedData::Manufacturer(company),
+                ) => (id, manufacturer_data.remove(&company)),
+                _ => continue,
+            };
+            let Some(data) = data.filter(|_| id == sensor.id()) else {
+                continue;
+            };
+
+            match self.handle_payload(&data, Source::Advertisement(decoder.as_ref()))? {
+                Handled::Reading => {
+                    self.send_rssi(sensor).await?;
+                    egui_ctx.request_repaint()
+                }
+                Handled::Dropped => {}
+                Handled::Taken => break,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Scan for `scan_duration` and look for the sensor, scanning again up to
+    /// `max_scan_retries` times while it is not found
+    async fn scan<C: Central>(
+        &self,
+        central: &C,
+    ) -> Result<(C::Peripheral, DecoderKind), BleTemperatureError> {
+        central.start_scan(ScanFilter::default()).await?;
+
+        let mut retries = 0;
+        loop {
+            tokio::time::sleep(self.scan_duration).await;
+            match self.find_sensor(central).await {
+                Err(BleTemperatureError::SensorNotFound) if retries < self.max_scan_retries => {
+                    retries += 1;
+                    info!(
+                        "sensor not found, scanning again ({}/{})",
+                        retries, self.max_scan_retries
+                    );
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// `scan`, failing once the `idle_timeout` ran out while the sensor was
+    /// never found
+    async fn scan_before_idle_timeout<C: Central>(
+        &mut self,
+        central: &C,
+    ) -> Result<(C::Peripheral, DecoderKind), BleTemperatureError> {
+        let Some((deadline, timeout)) = self.idle_deadline.zip(self.idle_timeout) else {
+            return self.scan(central).await;
+        };
+        tokio::select! {
+            found = self.scan(central) => {
+                if found.is_ok() {
+                    self.idle_deadline = None;
+                }
+                found
+            }
+            _ = tokio::time::sleep_until(deadline.into()) => {
+                let _ = central.stop_scan().await;
+                Err(BleTemperatureError::IdleTimeout(timeout.as_secs()))
+            }
+        }
+    }
+
+    async fn find_sensor<C: Central>(
+        &self,
+        central: &C,
+    ) -> Result<(C::Peripheral, DecoderKind), BleTemperatureError> {
+        // filtered before detection, so that any beacon nearby is not taken
+        // for the sensor
+        for (p, properties) in scan::matching(central, &self.filter).await? {
+            if !self.address_type.matches(properties.address_type) {
+                debug!(
+                    "skipping {}: address type differs",
+                    p.address().to_colon_hex()
+                );
+                continue;
+            }
+            // not every platform reports the signal strength
+            if let (Some(minimum), Some(rssi)) = (self.rssi_minimum, properties.rssi) {
+                if rssi < minimum {
+                    debug!(
+                        "skipping {}: signal {} dBm is too weak",
+                        p.address().to_colon_hex(),
+                        rssi
+                    );
+                    continue;
+                }
+            }
+
+            info!("discover sensor: {}", peripheral_name(&p).await);
+
+            let kind = self
+                .decoder
+                .unwrap_or_else(|| DecoderKind::detect(&properties));
+            if kind.advertised_data().is_some() && kind.is_advertised(&properties) {
+                info!(
+                    "discover {:?} advertiser: {}",
+                    kind,
+                    p.address().to_colon_hex()
+                );
+            }
+            return Ok((p, kind));
+        }
+
+        Err(BleTemperatureError::SensorNotFound)
+    }
+}
+
+/// Ticks every `STREAM_STATS_INTERVAL`, the first one an interval from now
+fn stream_stats_interval() -> tokio::time::Interval {
+    let start = tokio::time::Instant::now() + STREAM_STATS_INTERVAL;
+    tokio::time::interval_at(start, STREAM_STATS_INTERVAL)
+}
+
+pub async fn first_adapter() -> Result<Adapter, BleTemperatureError> {
+    let manager = Manager::new().await?;
+    let adapters = manager.adapters().await?;
+    adapters
+        .into_iter()
+        .next()
+        .ok_or(BleTemperatureError::NoAdapter)
+}
+
+/// A failing `--csv-append` file is reported, but does not stop the readings
+fn append_csv(log: &mut Option<CsvSink<BufWriter<File>>>, measurement: &TemperatureMeasurement) {
+    if let Some(log) = log {
+        if let Err(e) = log.write_reading(measurement) {
+            warn!("failed to append reading to CSV log: {}", e);
+        }
+    }
+}
+
+/// Local name of `p`, or its address for sensors that advertise none
+pub async fn peripheral_name<P: btleplug::api::Peripheral>(p: &P) -> String {
+    match p.properties().await {
+        Ok(Some(PeripheralProperties {
+            local_name: Some(name),
+            ..
+        })) => name,
+        _ => p.address().to_colon_hex(),
+    }
+}
+
+/// The characteristic `uuid` in `chars`, as discovered on a peripheral
+pub fn find_characteristic(
+    chars: &BTreeSet<Characteristic>,
+    uuid: Uuid,
+) -> Result<&Characteristic, BleTemperatureError> {
+    chars
+        .iter()
+        .find(|c| c.uuid == uuid)
+        .ok_or(BleTemperatureError::CharacteristicNotFound)
+}
+
+/// Sensors connected to before, tried again without scanning. Typed by the
+/// peripherals of one `Central`, so it lives in `run_on` next to the adapter
+/// rather than in `TemperatureSendor`
+struct BleCache<P> {
+    known_peripherals: HashMap<BDAddr, P>,
+}
+
+impl<P> Default for BleCache<P> {
+    fn default() -> Self {
+        Self {
+            known_peripherals: HashMap::new(),
+        }
+    }
+}
+
+impl<P: btleplug::api::Peripheral> BleCache<P> {
+    /// A known peripheral that is still, or again, connected. Each connection
+    /// attempt is bounded by `timeout`, and a sensor that fails or times out
+    /// counts as a miss
+    async fn connect(
+        &mut self,
+        timeout: Duration,
+        bus: &EventBus,
+    ) -> Result<Option<P>, SendError<Event>> {
+        let mut failed = Vec::new();
+        let mut connected = None;
+
+        for (address, p) in &self.known_peripherals {
+            if p.is_connected().await.unwrap_or(false) {
+                info!("reusing connection to {}", address.to_colon_hex());
+                connected = Some(p.clone());
+                break;
+            }
+
+            info!("connecting to known sensor: {}", address.to_colon_hex());
+            bus.send(Event::State(ConnectionState::Connecting {
+                address: address.to_colon_hex(),
+            }))?;
+            match tokio::time::timeout(timeout, p.connect()).await {
+                Ok(Ok(())) => {
+                    connected = Some(p.clone());
+                    break;
+                }
+                Ok(Err(e)) => {
+                    warn!(
+                        "direct connection to {} failed: {}",
+                        address.to_colon_hex(),
+                        e
+                    );
+                    failed.push(*address);
+                }
+                Err(_) => {
+                    warn!(
+                        "direct connection to {} timed out after {:?}",
+                        address.to_colon_hex(),
+                        timeout
+                    );
+                    failed.push(*address);
+                }
+            }
+        }
+
+        // forgotten until a scan finds them again
+        for address in failed {
+            self.known_peripherals.remove(&address);
+        }
+        Ok(connected)
+    }
+}
+
+/// Counts a connection in `Metrics` while alive
+struct ConnectionGuard(Arc<Metrics>);
+
+impl ConnectionGuard {
+    fn new(metrics: &Arc<Metrics>) -> Self {
+        metrics.connections.fetch_add(1, Ordering::Relaxed);
+        Self(metrics.clone())
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use btleplug::api::bleuuid::uuid_from_u16;
+    use btleplug::api::{AddressType as Reported, CharPropFlags};
+
+    use super::*;
+    use crate::decode::TEMPERATURE_MEASUREMENT;
+
+    fn characteristic(uuid: Uuid) -> Characteristic {
+        Characteristic {
+            uuid,
+            service_uuid: uuid_from_u16(0x1809),
+            properties: CharPropFlags::INDICATE,
+            descriptors: BTreeSet::new(),
+        }
+    }
+
+    #[test]
+    fn finds_characteristic_by_uuid() {
+        let chars = BTreeSet::from([
+            characteristic(uuid_from_u16(0x2a1d)),
+            characteristic(TEMPERATURE_MEASUREMENT),
+        ]);
+        let found = find_characteristic(&chars, TEMPERATURE_MEASUREMENT).unwrap();
+        assert_eq!(found.uuid, TEMPERATURE_MEASUREMENT);
+    }
+
+    #[test]
+    fn missing_characteristic_is_an_error() {
+        let chars = BTreeSet::from([characteristic(uuid_from_u16(0x2a1d))]);
+        assert!(matches!(
+            find_characteristic(&chars, TEMPERATURE_MEASUREMENT),
+            Err(BleTemperatureError::CharacteristicNotFound)
+        ));
+    }
+
+    #[test]
+    fn address_type_matches_reported_type() {
+        assert!(AddressType::Public.matches(Some(Reported::Public)));
+        assert!(!AddressType::Public.matches(Some(Reported::Random)));
+        assert!(AddressType::Random.matches(Some(Reported::Random)));
+        assert!(AddressType::Auto.matches(Some(Reported::Random)));
+        // not every platform reports it
+        assert!(AddressType::Public.matches(None));
+    }
+
+    #[test]
+    fn simulated_decode_failures_are_counted() {
+        let mut simulated = SimulatedSensor::new(0);
+        simulated.interval = Duration::from_millis(1);
+        let bus = EventBus::new(1024);
+        let mut events = bus.subscriber();
+        let mut sensor = TemperatureSendorBuilder::new()
+            .simulate(simulated)
+            .inject_errors(1.0)
+            .build(bus);
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let (ctx, cancel) = (Context::default(), CancellationToken::new());
+            let stop = cancel.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                stop.cancel();
+            });
+            // the simulation only ends when cancelled
+            sensor.run(&ctx, &cancel).await.unwrap();
+        });
+
+        let notifications = sensor.metrics.notifications.load(Ordering::Relaxed);
+        assert!(notifications > 0);
+        assert_eq!(
+            sensor.metrics.decode_failures.load(Ordering::Relaxed),
+            notifications
+        );
+        assert_eq!(sensor.metrics.decoded.load(Ordering::Relaxed), 0);
+        // and warned about every ten
+        assert_eq!(sensor.consecutive_failures as u64, notifications);
+        assert!(std::iter::from_fn(|| events.try_recv().ok())
+            .skip(1)
+            .all(|event| matches!(event, Event::Log(LogEvent::DecodeFailed { .. }))));
+    }
+
+    #[cfg(feature = "mock-ble")]
+    mod mock_flow {
+        use btleplug::api::Manager as _;
+        use regex::Regex;
+        use tokio::runtime::Runtime;
+
+        use super::super::mock::{MockAdapter, MockManager, MockPeripheral};
+        use super::*;
+        use crate::decode::EDDYSTONE_SERVICE;
+
+        /// 23.4°C
+        const READING: [u8; 5] = [0x00, 0x68, 0x5b, 0x00, 0x00];
+
+        fn sensor(filter: &str) -> TemperatureSendor {
+            TemperatureSendorBuilder::new()
+                .device_filter(DeviceFilter::ByName(Regex::new(filter).unwrap()))
+                .scan_duration(Duration::ZERO)
+                .build(EventBus::new(16))
+        }
+
+        fn manager() -> MockManager {
+            MockManager::new(vec![MockAdapter::new(vec![
+                MockPeripheral::thermometer(BDAddr::from([1, 0, 0, 0, 0, 1]), "Heart Rate", vec![]),
+                MockPeripheral::thermometer(
+                    BDAddr::from([1, 0, 0, 0, 0, 2]),
+                    "Temperature01",
+                    vec![READING.to_vec(); 3],
+                ),
+            ])])
+        }
+
+        #[test]
+        fn scan_connect_subscribe() {
+            Runtime::new().unwrap().block_on(async {
+                let adapter = manager().adapters().await.unwrap().remove(0);
+                let discovered: Vec<_> = adapter.events().await.unwrap().collect().await;
+                assert_eq!(discovered.len(), 2);
+
+                let (peripheral, kind) = sensor("(?i)temperature").scan(&adapter).await.unwrap();
+                assert_eq!(peripheral.address(), BDAddr::from([1, 0, 0, 0, 0, 2]));
+                assert_eq!(kind, DecoderKind::HealthThermometer);
+                assert_eq!(
+                    adapter
+                        .peripheral(&peripheral.id())
+                        .await
+                        .unwrap()
+                        .address(),
+                    peripheral.address()
+                );
+
+                peripheral.connect().await.unwrap();
+                assert!(peripheral.is_connected().await.unwrap());
+                let chars = peripheral.characteristics();
+                let notify_char = find_characteristic(&chars, TEMPERATURE_MEASUREMENT).unwrap();
+                peripheral.subscribe(notify_char).await.unwrap();
+
+                let readings: Vec<_> = peripheral
+                    .notifications()
+                    .await
+                    .unwrap()
+                    .filter_map(|n| async move {
+                        TemperatureMeasurement::try_from(&n.value[..])
+                            .ok()
+                            .map(f32::from)
+                    })
+                    .collect()
+                    .await;
+                assert_eq!(readings, vec![23.4; 3]);
+            });
+        }
+
+        #[test]
+        fn beacons_are_only_taken_when_the_filter_matches() {
+            let mut beacon =
+                MockPeripheral::thermometer(BDAddr::from([1, 0, 0, 0, 0, 3]), "Beacon", vec![]);
+            // TLM frame, 21.5°C
+            beacon.properties.service_data.insert(
+                EDDYSTONE_SERVICE,
+                vec![0x20, 0x00, 0x0b, 0xb8, 0x15, 0x80, 0, 0, 0, 0, 0, 0, 0, 0],
+            );
+            let mut peripherals = vec![beacon];
+            peripherals.extend(manager().adapters[0].peripherals.clone());
+            let adapter = MockAdapter::new(peripherals);
+
+            Runtime::new().unwrap().block_on(async {
+                let (peripheral, kind) = sensor("(?i)temperature").scan(&adapter).await.unwrap();
+                assert_eq!(peripheral.address(), BDAddr::from([1, 0, 0, 0, 0, 2]));
+                assert_eq!(kind, DecoderKind::HealthThermometer);
+
+                let (peripheral, kind) = sensor("Beacon").scan(&adapter).await.unwrap();
+                assert_eq!(peripheral.address(), BDAddr::from([1, 0, 0, 0, 0, 3]));
+                assert_eq!(kind, DecoderKind::EddystoneTlm);
+            });
+        }
+
+        #[test]
+        fn reconnects_after_the_sensor_drops() {
+            let bus = EventBus::new(64);
+            let mut events = bus.subscriber();
+            let mut sensor = TemperatureSendorBuilder::new()
+                .device_filter(DeviceFilter::ByName(Regex::new("(?i)temperature").unwrap()))
+                .scan_duration(Duration::ZERO)
+                .backoff(Duration::from_millis(1), Duration::from_millis(1))
+                .build(bus);
+            let adapter = manager().adapters.remove(0);
+
+            Runtime::new().unwrap().block_on(async {
+                let (ctx, cancel) = (Context::default(), CancellationToken::new());
+                let stop = cancel.clone();
+                let watcher = tokio::spawn(async move {
+                    let mut seen = Vec::new();
+                    while let Ok(event) = events.recv().await {
+                        match event {
+                            Event::Connected { .. } => seen.push("connected"),
+                            Event::State(ConnectionState::Scanning) => seen.push("scanning"),
+                            Event::State(ConnectionState::Connecting { .. }) => {
+                                seen.push("connecting")
+                            }
+                            Event::State(ConnectionState::Reconnecting { attempt, .. }) => {
+                                assert_eq!(attempt, 1);
+                                seen.push("reconnecting")
+                            }
+                            _ => continue,
+                        }
+                        if seen.len() == 6 {
+                            stop.cancel();
+                            break;
+                        }
+                    }
+                    seen
+                });
+
+                // the three notifications end the first session, and the
+                // second connects to the cached sensor without scanning
+                sensor.run_on(&adapter, &ctx, &cancel).await.unwrap();
+                assert_eq!(
+                    watcher.await.unwrap(),
+                    [
+                        "scanning",
+                        "connecting",
+                        "connected",
+                        "reconnecting",
+                        "connecting",
+                        "connected"
+                    ]
+                );
+            });
+        }
+
+        #[test]
+        fn leftovers_of_an_old_session_are_dropped() {
+            let bus = EventBus::new(64);
+            let mut events = bus.subscriber();
+            let mut sensor = TemperatureSendorBuilder::new()
+                .device_filter(DeviceFilter::ByName(Regex::new("(?i)temperature").unwrap()))
+                .scan_duration(Duration::ZERO)
+                .build(bus);
+            let adapter = manager().adapters.remove(0);
+
+            Runtime::new().unwrap().block_on(async {
+                let (ctx, cancel) = (Context::default(), CancellationToken::new());
+                sensor.session(&adapter, &ctx, &cancel).await.unwrap();
+                let peripheral = adapter
+                    .peripherals()
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .find(|p| p.address() == BDAddr::from([1, 0, 0, 0, 0, 2]))
+                    .unwrap();
+                // left open by a stack that keeps the stream of the old connection
+                let mut old = sensor.session_notifications(&peripheral).await.unwrap();
+
+                // the sensor dropped after its three notifications, reconnect
+                sensor.session(&adapter, &ctx, &cancel).await.unwrap();
+                let (context, leftover) = old.next().await.unwrap();
+                assert!(matches!(
+                    sensor.handle_payload(&leftover.value, Source::Notification(context)),
+                    Ok(Handled::Dropped)
+                ));
+            });
+
+            let mut readings = 0;
+            while let Ok(event) = events.try_recv() {
+                if let Event::Reading(reading) = event {
+                    assert_eq!(reading.celsius, 23.4);
+                    readings += 1;
+                }
+            }
+            // both sessions, without the leftover
+            assert_eq!(readings, 6);
+        }
+
+        #[test]
+        fn notifications_reach_the_ui_channel() {
+            let expected: Vec<f32> = (0..20).map(|i| 20.0 + i as f32 * 0.25).collect();
+            let payloads = expected
+                .iter()
+                .map(|celsius| {
+                    let [b0, b1, b2, _] = ((celsius * 1000.0) as u32).to_le_bytes();
+                    vec![0x00, b0, b1, b2, 0x00]
+                })
+                .collect();
+            let adapter = MockAdapter::new(vec![MockPeripheral::thermometer(
+                BDAddr::from([1, 0, 0, 0, 0, 2]),
+                "Temperature01",
+                payloads,
+            )]);
+
+            let bus = EventBus::new(1024);
+            let metrics = Arc::new(Metrics::default());
+            let mut sensor = TemperatureSendorBuilder::new()
+                .device_filter(DeviceFilter::ByName(Regex::new("(?i)temperature").unwrap()))
+                .scan_duration(Duration::ZERO)
+                // long enough to be cancelled before reconnecting
+                .backoff(Duration::from_secs(60), Duration::from_secs(60))
+                .metrics(metrics.clone())
+                .build(bus.clone());
+            let (tx, rx) = std::sync::mpsc::channel();
+
+            Runtime::new().unwrap().block_on(async {
+                let forwarder = tokio::spawn(forward(bus.subscriber(), tx, metrics));
+                let mut events = bus.subscriber();
+                drop(bus);
+
+                let (ctx, cancel) = (Context::default(), CancellationToken::new());
+                let stop = cancel.clone();
+                tokio::spawn(async move {
+                    let mut readings = 0;
+                    while readings < 20 {
+                        match events.recv().await {
+                            Ok(Event::Reading(_)) => readings += 1,
+                            Ok(_) => {}
+                            Err(_) => break,
+                        }
+                    }
+                    stop.cancel();
+                });
+
+                sensor.run_on(&adapter, &ctx, &cancel).await.unwrap();
+                assert!(cancel.is_cancelled());
+                // closes the bus, which ends the forwarding
+                drop(sensor);
+                forwarder.await.unwrap();
+            });
+
+            let (readings, sequence_ids): (Vec<f32>, Vec<Option<u8>>) = rx
+                .try_iter()
+                .filter_map(|event| match event {
+                    Event::Reading(reading) => Some((reading.celsius, reading.sequence_id)),
+                    _ => None,
+                })
+                .unzip();
+            assert_eq!(readings, expected);
+            assert_eq!(sequence_ids, (0..20).map(Some).collect::<Vec<_>>());
+            assert!(matches!(
+                rx.try_recv(),
+                Err(std::sync::mpsc::TryRecvError::Disconnected)
+            ));
+        }
+
+        #[test]
+        fn peripheral_name_falls_back_to_the_address() {
+            Runtime::new().unwrap().block_on(async {
+                let address = BDAddr::from([1, 0, 0, 0, 0, 2]);
+                let mut sensor = MockPeripheral::thermometer(address, "Temperature01", vec![]);
+                assert_eq!(peripheral_name(&sensor).await, "Temperature01");
+
+                sensor.properties.local_name = None;
+                assert_eq!(peripheral_name(&sensor).await, "01:00:00:00:00:02");
+            });
+        }
+
+        #[test]
+        fn scan_devices_lists_the_matching_peripherals() {
+            Runtime::new().unwrap().block_on(async {
+                let adapter = manager().adapters().await.unwrap().remove(0);
+                let filter = DeviceFilter::ByName(Regex::new("(?i)temperature").unwrap());
+                let found = scan_devices(&adapter, Duration::ZERO, &filter)
+                    .await
+                    .unwrap();
+                assert_eq!(
+                    found,
+                    vec![ScanResult {
+                        address: BDAddr::from([1, 0, 0, 0, 0, 2]),
+                        name: Some("Temperature01".to_owned()),
+                        rssi: None,
+                        services: vec![btleplug::api::bleuuid::uuid_from_u16(0x1809)],
+                    }]
+                );
+            });
+        }
+
+        #[test]
+        fn scan_gives_up_after_the_retries() {
+            Runtime::new().unwrap().block_on(async {
+                let adapter = manager().adapters().await.unwrap().remove(0);
+                // nothing is known before scanning
+                assert!(adapter.peripherals().await.unwrap().is_empty());
+
+                let result = sensor("Kitchen").scan(&adapter).await;
+                assert!(matches!(result, Err(BleTemperatureError::SensorNotFound)));
+            });
+        }
+    }
+}
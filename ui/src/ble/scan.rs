@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use btleplug::api::{BDAddr, Central, Peripheral as _, PeripheralProperties, ScanFilter};
+use log::debug;
+use uuid::Uuid;
+
+use super::{BdAddrExt, DeviceFilter};
+use crate::error::BleTemperatureError;
+
+/// A peripheral seen while scanning
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScanResult {
+    pub address: BDAddr,
+    pub name: Option<String>,
+    /// Signal strength in dBm, not reported on every platform
+    pub rssi: Option<i16>,
+    /// Advertised services
+    pub services: Vec<Uuid>,
+}
+
+impl From<&PeripheralProperties> for ScanResult {
+    fn from(properties: &PeripheralProperties) -> Self {
+        Self {
+            address: properties.address,
+            name: properties.local_name.clone(),
+            rssi: properties.rssi,
+            services: properties.services.clone(),
+        }
+    }
+}
+
+/// Scan for `duration` and list the peripherals `filter` matches. The scan
+/// keeps running for advertisements, stopping it is up to the caller
+pub async fn scan_devices<C: Central>(
+    adapter: &C,
+    duration: Duration,
+    filter: &DeviceFilter,
+) -> Result<Vec<ScanResult>, BleTemperatureError> {
+    adapter.start_scan(ScanFilter::default()).await?;
+    tokio::time::sleep(duration).await;
+    Ok(matching(adapter, filter)
+        .await?
+        .iter()
+        .map(|(_, properties)| ScanResult::from(properties))
+        .collect())
+}
+
+/// Peripherals found so far that `filter` matches, with their properties. The
+/// enumeration behind `scan_devices`, for callers that keep a scan running
+pub(super) async fn matching<C: Central>(
+    adapter: &C,
+    filter: &DeviceFilter,
+) -> Result<Vec<(C::Peripheral, PeripheralProperties)>, BleTemperatureError> {
+    let mut found = discovered(adapter).await?;
+    found.retain(|(_, properties)| filter.matches(properties));
+    Ok(found)
+}
+
+/// Peripherals found so far, with their properties
+async fn discovered<C: Central>(
+    adapter: &C,
+) -> Result<Vec<(C::Peripheral, PeripheralProperties)>, BleTemperatureError> {
+    let mut found = Vec::new();
+    for p in adapter.peripherals().await? {
+        match p.properties().await? {
+            Some(properties) => found.push((p, properties)),
+            None => debug!(
+                "skipping {}: properties unavailable",
+                p.address().to_colon_hex()
+            ),
+        }
+    }
+    Ok(found)
+}
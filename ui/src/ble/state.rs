@@ -0,0 +1,98 @@
+use std::fmt;
+use std::time::Instant;
+
+/// What the sensor task is doing, as shown in the status bar
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub enum ConnectionState {
+    #[default]
+    Idle,
+    Scanning,
+    Connecting {
+        address: String,
+    },
+    Connected {
+        name: String,
+    },
+    Reconnecting {
+        attempt: u32,
+        /// Attempts before giving up, if limited
+        max_attempts: Option<u32>,
+        next_retry: Instant,
+    },
+    /// No Bluetooth hardware, or it is switched off
+    NoAdapter,
+    /// The sensor task stopped and waits for a retry
+    Error(String),
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionState::Idle => write!(f, "Idle"),
+            ConnectionState::Scanning => write!(f, "Scanning for sensors…"),
+            ConnectionState::Connecting { address } => write!(f, "Connecting to {}", address),
+            ConnectionState::Connected { name } => write!(f, "Connected: {}", name),
+            ConnectionState::Reconnecting {
+                attempt,
+                max_attempts,
+                next_retry,
+            } => {
+                // zero once the retry is due, rounded up before that
+                let remaining = next_retry.saturating_duration_since(Instant::now());
+                write!(f, "Reconnecting (attempt {}", attempt)?;
+                if let Some(max) = max_attempts {
+                    write!(f, " of {}", max)?;
+                }
+                write!(f, ", next in {}s)", remaining.as_secs_f32().ceil())
+            }
+            ConnectionState::NoAdapter => {
+                write!(f, "No Bluetooth adapter found. Please enable Bluetooth.")
+            }
+            ConnectionState::Error(e) => write!(f, "Error: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn reconnecting_counts_down() {
+        let state = ConnectionState::Reconnecting {
+            attempt: 3,
+            max_attempts: None,
+            next_retry: Instant::now() + Duration::from_millis(7500),
+        };
+        assert_eq!(state.to_string(), "Reconnecting (attempt 3, next in 8s)");
+    }
+
+    #[test]
+    fn reconnecting_shows_the_limit() {
+        let state = ConnectionState::Reconnecting {
+            attempt: 3,
+            max_attempts: Some(10),
+            next_retry: Instant::now() + Duration::from_millis(7500),
+        };
+        assert_eq!(
+            state.to_string(),
+            "Reconnecting (attempt 3 of 10, next in 8s)"
+        );
+    }
+
+    #[test]
+    fn overdue_reconnect_shows_zero() {
+        let Some(past) = Instant::now().checked_sub(Duration::from_secs(5)) else {
+            return;
+        };
+        let state = ConnectionState::Reconnecting {
+            attempt: 1,
+            max_attempts: None,
+            next_retry: past,
+        };
+        assert_eq!(state.to_string(), "Reconnecting (attempt 1, next in 0s)");
+    }
+}
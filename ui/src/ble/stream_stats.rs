@@ -0,0 +1,83 @@
+use std::fmt;
+
+use log::debug;
+
+use crate::decode::TemperatureMeasurement;
+
+/// Notifications between two stream stats lines in the log
+const LOG_EVERY: u64 = 100;
+
+/// What the notification stream delivered, telling sensor firmware bugs
+/// (well-formed but undecodable) apart from transport errors (empty or cut short)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    pub total: u64,
+    /// Notifications without a value
+    pub empty: u64,
+    /// Neither empty nor the length of a Temperature Measurement
+    pub wrong_length: u64,
+    pub decoded: u64,
+}
+
+impl StreamStats {
+    /// Count a notification of `value`, `decoded` when it gave a reading
+    pub fn record(&mut self, value: &[u8], decoded: bool) {
+        self.total += 1;
+        match value.len() {
+            0 => self.empty += 1,
+            TemperatureMeasurement::LEN => {}
+            _ => self.wrong_length += 1,
+        }
+        if decoded {
+            self.decoded += 1;
+        }
+        if self.total.is_multiple_of(LOG_EVERY) {
+            debug!("notification stream: {}", self);
+        }
+    }
+
+    /// Share of the notifications decoded, in percent
+    pub fn success_rate(&self) -> f64 {
+        self.decoded as f64 * 100.0 / self.total.max(1) as f64
+    }
+}
+
+impl fmt::Display for StreamStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} decoded ({:.1}%), {} empty, {} of the wrong length",
+            self.decoded,
+            self.total,
+            self.success_rate(),
+            self.empty,
+            self.wrong_length
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notifications_are_counted_by_shape() {
+        let mut stats = StreamStats::default();
+        stats.record(&[], false);
+        stats.record(&[0x00, 0x68], false);
+        stats.record(&[0x00, 0x68, 0x5b, 0x00, 0x00], true);
+        stats.record(&[0x00, 0xff, 0xff, 0x7f, 0x00], false);
+
+        assert_eq!(
+            stats,
+            StreamStats {
+                total: 4,
+                empty: 1,
+                wrong_length: 1,
+                decoded: 1,
+            }
+        );
+        assert_eq!(stats.success_rate(), 25.0);
+        assert_eq!(StreamStats::default().success_rate(), 0.0);
+    }
+}
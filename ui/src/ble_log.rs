@@ -0,0 +1,46 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use btleplug::api::ValueNotification;
+use log::warn;
+
+use crate::clock;
+
+/// Timestamped record of raw BLE traffic, similar to `hcidump` output
+pub struct BleEventLog {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl BleEventLog {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: Mutex::new(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    /// Record a lifecycle event such as `scan` or `connect AA:BB:..`
+    pub fn event(&self, event: &str) {
+        self.write_line(event);
+    }
+
+    /// Record a notification before it is decoded
+    pub fn notification(&self, notification: &ValueNotification) {
+        let hex: Vec<String> = notification
+            .value
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        self.write_line(&format!("> {} {}", notification.uuid, hex.join(" ")));
+    }
+
+    fn write_line(&self, line: &str) {
+        let now = clock::format(SystemTime::now(), "%Y-%m-%d %H:%M:%S%.3f");
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(e) = writeln!(writer, "{} {}", now, line).and_then(|_| writer.flush()) {
+            warn!("failed to write BLE event log: {}", e);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use egui::Color32;
+use egui_plot::PlotPoint;
+use serde::{Deserialize, Serialize};
+
+/// How readings are drawn on the plot
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum ChartType {
+    Line,
+    Scatter,
+    Step,
+}
+
+/// Hold each value until the next reading: `[x0, y0], [x1, y0], [x1, y1], ...`
+pub fn to_steps(points: &[[f64; 2]]) -> Vec<[f64; 2]> {
+    let mut steps = Vec::with_capacity(points.len() * 2);
+    for pair in points.windows(2) {
+        steps.push(pair[0]);
+        steps.push([pair[1][0], pair[0][1]]);
+    }
+    steps.extend(points.last());
+    steps
+}
+
+/// Colors assigned to sensors in the order they connect
+pub const PALETTE: [[u8; 3]; 8] = [
+    [100, 200, 100],
+    [31, 119, 180],
+    [255, 127, 14],
+    [214, 39, 40],
+    [148, 103, 189],
+    [140, 86, 75],
+    [227, 119, 194],
+    [23, 190, 207],
+];
+
+/// Line appearance of one sensor
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SensorStyle {
+    pub color: [u8; 3],
+    pub width: f32,
+}
+
+impl SensorStyle {
+    /// Default style of the `n`-th connected sensor
+    pub fn nth(n: usize) -> Self {
+        Self {
+            color: PALETTE[n % PALETTE.len()],
+            width: 1.5,
+        }
+    }
+
+    pub fn color32(&self) -> Color32 {
+        let [r, g, b] = self.color;
+        Color32::from_rgb(r, g, b)
+    }
+}
+
+/// The point closest to `cursor`
+pub fn find_nearest(points: &[(f64, f64)], cursor: PlotPoint) -> Option<(f64, f64)> {
+    points.iter().copied().min_by(|a, b| {
+        let distance = |(x, y): &(f64, f64)| (x - cursor.x).powi(2) + (y - cursor.y).powi(2);
+        distance(a).total_cmp(&distance(b))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_hold_each_value_until_the_next_reading() {
+        assert!(to_steps(&[]).is_empty());
+        assert_eq!(to_steps(&[[0.0, 20.0]]), vec![[0.0, 20.0]]);
+
+        let points = [[0.0, 20.0], [1.0, 21.0], [3.0, 19.5]];
+        let steps = to_steps(&points);
+        assert_eq!(steps.len(), 2 * points.len() - 1);
+        assert_eq!(
+            steps,
+            vec![
+                [0.0, 20.0],
+                [1.0, 20.0],
+                [1.0, 21.0],
+                [3.0, 21.0],
+                [3.0, 19.5],
+            ]
+        );
+        // horizontal, then vertical
+        for (i, pair) in steps.windows(2).enumerate() {
+            let axis = if i % 2 == 0 { 1 } else { 0 };
+            assert_eq!(pair[0][axis], pair[1][axis], "segment {}", i);
+        }
+    }
+
+    #[test]
+    fn nearest_point_to_the_cursor() {
+        let cursor = |x, y| PlotPoint::new(x, y);
+        assert_eq!(find_nearest(&[], cursor(0.0, 20.0)), None);
+
+        let points = [(0.0, 20.0), (10.0, 21.0), (20.0, 19.0)];
+        assert_eq!(find_nearest(&points, cursor(-5.0, 20.0)), Some((0.0, 20.0)));
+        assert_eq!(
+            find_nearest(&points, cursor(30.0, 19.0)),
+            Some((20.0, 19.0))
+        );
+        assert_eq!(find_nearest(&points, cursor(7.0, 20.5)), Some((10.0, 21.0)));
+        assert_eq!(
+            find_nearest(&points, cursor(13.0, 20.0)),
+            Some((10.0, 21.0))
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Milliseconds since the Unix epoch
+pub fn unix_ms(at: SystemTime) -> u128 {
+    at.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+/// `at` in local time as `strftime`-like `format`
+#[cfg(feature = "chrono")]
+pub fn format(at: SystemTime, format: &str) -> String {
+    chrono::DateTime::<chrono::Local>::from(at)
+        .format(format)
+        .to_string()
+}
+
+/// Unix milliseconds, whatever the `format`: there is no calendar without the
+/// `chrono` feature
+#[cfg(not(feature = "chrono"))]
+pub fn format(at: SystemTime, _format: &str) -> String {
+    unix_ms(at).to_string()
+}
+
+/// A CSV timestamp, Unix milliseconds or the `2024-01-15T14:32:05.123` local
+/// time written with the `chrono` feature
+pub fn parse_ms(s: &str) -> Option<u64> {
+    if let Ok(ms) = s.parse() {
+        return Some(ms);
+    }
+    #[cfg(feature = "chrono")]
+    {
+        let local = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.3f").ok()?;
+        let at = local.and_local_timezone(chrono::Local).earliest()?;
+        u64::try_from(at.timestamp_millis()).ok()
+    }
+    #[cfg(not(feature = "chrono"))]
+    None
+}
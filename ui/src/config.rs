@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::alarm::TemperatureThreshold;
+use crate::alert_sound::AlertSoundConfig;
+use crate::chart::{ChartType, SensorStyle};
+use crate::flush::{self, Flusher};
+use crate::unit::TemperatureUnit;
+
+/// Temperature axis shown when nothing else is configured
+const DEFAULT_RANGE_CELSIUS: RangeInclusive<f32> = 15.0..=30.0;
+
+/// Space kept around the alert thresholds on the temperature axis
+const ALERT_PADDING_CELSIUS: f32 = 2.0;
+
+/// Window icon, rendered from `assets/thermometer.svg` by the build script
+const ICON_SIZE: u32 = 32;
+
+/// Size of one in-memory history record. `Instant` takes 16 bytes on 64-bit
+/// targets and the tuple is padded to 24, twice the 12 of the bare fields
+const RECORD_SIZE: usize = std::mem::size_of::<(Instant, f32)>();
+
+/// Declares `Config` and keeps each field's doc comment around for `generate_example`.
+/// Optional fields give an `example` value that is written out commented.
+macro_rules! config {
+    (@example) => {
+        None
+    };
+    (@example $example:literal) => {
+        Some(stringify!($example))
+    };
+    ($($(#[doc = $doc:literal])* $field:ident: $ty:ty = $default:expr $(; example $example:literal)?,)*) => {
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        #[serde(default)]
+        pub struct Config {
+            $($(#[doc = $doc])* pub $field: $ty,)*
+        }
+
+        impl Default for Config {
+            fn default() -> Self {
+                Self {
+                    $($field: $default,)*
+                }
+            }
+        }
+
+        impl Config {
+            #[allow(clippy::type_complexity)]
+            const DOCS: &'static [(&'static str, &'static [&'static str], Option<&'static str>)] =
+                &[$((stringify!($field), &[$($doc),*], config!(@example $($example)?)),)*];
+        }
+    };
+}
+
+config! {
+    /// Name substring to match when scanning for sensors (case-insensitive regex)
+    sensor_name_filter: String = "(?i)temperature".into(),
+    /// Number of readings kept in memory, five minutes at the usual one reading per second
+    history_len: usize = 300,
+    /// Upper bound for the in-memory history, in MB
+    memory_limit_mb: usize = 32,
+    /// Move new readings to a compressed CSV file every N minutes (0 disables)
+    flush_interval_minutes: u64 = 0,
+    /// Directory for flushed CSV files
+    flush_dir: PathBuf = PathBuf::from("."),
+    /// Human labels for sensors, keyed by a device name substring or address,
+    /// e.g. `Temperature01 = "Kitchen"`
+    sensor_locations: HashMap<String, String> = HashMap::new(),
+    /// How readings are drawn: "line", "scatter" or "step"
+    chart_type: ChartType = ChartType::Line,
+    /// Line color and width per sensor name, e.g. `Temperature01 = { color = [100, 200, 100], width = 1.5 }`
+    sensor_styles: HashMap<String, SensorStyle> = HashMap::new(),
+    /// Lower end of the chart's temperature axis [default: 15, or 2 below the low alert]
+    y_min_celsius: Option<f32> = None; example 10.0,
+    /// Upper end of the chart's temperature axis [default: 30, or 2 above the high alert]
+    y_max_celsius: Option<f32> = None; example 35.0,
+    /// Readings above this temperature raise an alarm
+    alert_high_celsius: Option<f32> = None; example 30.0,
+    /// Readings below this temperature raise an alarm
+    alert_low_celsius: Option<f32> = None; example 15.0,
+    /// °C a reading must be back inside a threshold before its alarm can fire
+    /// again. 0.5 is above the jitter of typical sensors, which would
+    /// otherwise raise an alarm on every other reading near the threshold
+    alert_hysteresis_celsius: f32 = TemperatureThreshold::DEFAULT_HYSTERESIS,
+    /// Beep when a threshold is breached, at most once every 5 minutes
+    alert_sound: AlertSoundConfig = AlertSoundConfig::default(),
+    /// Seconds to scan before looking for the sensor. Most sensors advertise
+    /// at least once a second, 5 leaves room for slow advertising intervals
+    scan_duration_secs: u64 = 5,
+    /// Seconds to wait for a connection. Connecting usually takes 1-3 seconds,
+    /// a stalled attempt is given up well before the reconnect schedule notices
+    connect_timeout_secs: u64 = 15,
+    /// Scans to repeat when the sensor was not found, before backing off. 3
+    /// rides out a missed advertisement without scanning forever
+    max_scan_retries: u32 = 3,
+    /// °C added to every reading, for sensors that read consistently off.
+    /// 0 keeps readings as the sensor reports them
+    calibration_offset: f64 = 0.0,
+    /// Weight of the newest reading in the smoothed current temperature,
+    /// 1 disables smoothing. 0.3 evens out sensor noise while following a real
+    /// change within a few readings
+    smoothing_alpha: f64 = 0.3,
+    /// Unit of the temperatures in the statistics panel: "celsius" or
+    /// "fahrenheit". Celsius is what the sensors report
+    display_unit: TemperatureUnit = TemperatureUnit::Celsius,
+    /// Color scheme: "light", "dark" or "system" to follow the desktop setting
+    theme: Theme = Theme::Light,
+    /// Initial window width and height, in points
+    window_size: [f32; 2] = [600.0, 400.0],
+    /// Delay between reconnection attempts, doubling after each failure. Starts
+    /// at 1 second to recover quickly from a glitch and is capped at 60, so a
+    /// sensor that is away for long is still found within a minute of returning
+    reconnect: ReconnectConfig = ReconnectConfig::default(),
+}
+
+/// `[reconnect]` section of the config
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReconnectConfig {
+    pub initial_delay_secs: u64,
+    pub max_delay_secs: u64,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay_secs: 1,
+            max_delay_secs: 60,
+        }
+    }
+}
+
+/// Color scheme of the window
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+    System,
+}
+
+impl From<&Config> for eframe::NativeOptions {
+    fn from(config: &Config) -> Self {
+        let icon = egui::IconData {
+            rgba: include_bytes!(concat!(env!("OUT_DIR"), "/icon.rgba")).to_vec(),
+            width: ICON_SIZE,
+            height: ICON_SIZE,
+        };
+        let default_theme = match config.theme {
+            Theme::Dark => eframe::Theme::Dark,
+            // also the fallback when the desktop setting is unknown
+            Theme::Light | Theme::System => eframe::Theme::Light,
+        };
+
+        Self {
+            viewport: egui::ViewportBuilder::default()
+                .with_inner_size(config.window_size)
+                .with_icon(icon),
+            default_theme,
+            follow_system_theme: config.theme == Theme::System,
+            ..Default::default()
+        }
+    }
+}
+
+impl Config {
+    /// Temperature axis of the chart: `y_min_celsius` and `y_max_celsius` if
+    /// set, otherwise the default range widened to show the alert thresholds
+    pub fn temperature_range_celsius(&self) -> RangeInclusive<f32> {
+        let (mut min, mut max) = DEFAULT_RANGE_CELSIUS.into_inner();
+        if let Some(low) = self.alert_low_celsius {
+            min = min.min(low - ALERT_PADDING_CELSIUS);
+        }
+        if let Some(high) = self.alert_high_celsius {
+            max = max.max(high + ALERT_PADDING_CELSIUS);
+        }
+        self.y_min_celsius.unwrap_or(min)..=self.y_max_celsius.unwrap_or(max)
+    }
+
+    /// Load `path`, falling back to defaults when the file does not exist
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(toml::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// A `config.toml` with every key set to its default and documented
+    pub fn generate_example() -> String {
+        Self::example_document().to_string().trim_start().to_owned()
+    }
+
+    /// Add keys missing from `existing` without touching the ones already set
+    pub fn merge_example(existing: &str) -> Result<String, toml_edit::TomlError> {
+        let mut doc: DocumentMut = existing.parse()?;
+        merge_missing(doc.as_table_mut(), Self::example_document().as_table());
+        Ok(doc.to_string())
+    }
+
+    fn example_document() -> DocumentMut {
+        let mut doc: DocumentMut = toml::to_string(&Self::default())
+            .expect("default config is serializable")
+            .parse()
+            .expect("serialized config is valid toml");
+
+        // unset optional keys are written commented out, in front of the next key
+        let mut pending = String::new();
+        for (key, docs, example) in Self::DOCS {
+            let comment: String = docs.iter().map(|line| format!("#{}\n", line)).collect();
+
+            let prefix = format!("{}\n{}", pending, comment);
+            match doc.get_mut(key) {
+                Some(Item::Table(table)) => table.decor_mut().set_prefix(prefix),
+                Some(_) => {
+                    if let Some(mut key) = doc.as_table_mut().key_mut(key) {
+                        key.leaf_decor_mut().set_prefix(prefix);
+                    }
+                }
+                None => {
+                    pending = format!("{}# {} = {}\n", prefix, key, example.unwrap_or("\"\""));
+                    continue;
+                }
+            }
+            pending.clear();
+        }
+        if !pending.is_empty() {
+            // keep them above the first table so they stay top-level keys when uncommented
+            let first_table = doc.iter_mut().find_map(|(_, item)| item.as_table_mut());
+            match first_table {
+                Some(table) => {
+                    let prefix = table
+                        .decor()
+                        .prefix()
+                        .and_then(|p| p.as_str())
+                        .unwrap_or("");
+                    let prefix = format!("{}{}", pending, prefix);
+                    table.decor_mut().set_prefix(prefix);
+                }
+                None => doc.set_trailing(pending),
+            }
+        }
+
+        doc
+    }
+
+    /// Store the current value of `keys` in `path`, keeping everything else in the file
+    pub fn save_keys(&self, path: &Path, keys: &[&str]) -> Result<(), Box<dyn Error>> {
+        let existing = if path.exists() {
+            std::fs::read_to_string(path)?
+        } else {
+            String::new()
+        };
+
+        let mut doc: DocumentMut = existing.parse()?;
+        let current: DocumentMut = toml::to_string(self)?.parse()?;
+        for key in keys {
+            if let Some(item) = current.get(key) {
+                doc[key] = item.clone();
+            }
+        }
+
+        std::fs::write(path, doc.to_string())?;
+        Ok(())
+    }
+
+    /// Most readings that fit in the memory limit. A limit of 0 from the
+    /// config file counts as 1 MB, a history keeps at least one reading anyway
+    pub fn max_records(&self) -> usize {
+        self.memory_limit_mb.max(1) * 1024 * 1024 / RECORD_SIZE
+    }
+
+    /// History length after applying the memory limit
+    pub fn effective_history_len(&self) -> usize {
+        let max_records = self.max_records();
+        if self.history_len > max_records {
+            warn!(
+                "history length {} exceeds memory limit of {} MB, truncating to {}",
+                self.history_len, self.memory_limit_mb, max_records
+            );
+            return max_records;
+        }
+        self.history_len
+    }
+
+    pub fn flusher(&self) -> Option<Flusher> {
+        if self.flush_interval_minutes == 0 {
+            return None;
+        }
+
+        Some(Flusher::new(
+            self.flush_dir.clone(),
+            Duration::from_secs(self.flush_interval_minutes * 60),
+        ))
+    }
+
+    /// Files flushed by previous sessions, offered as overlays. Empty while
+    /// flushing is off
+    pub fn flushed_files(&self) -> Vec<PathBuf> {
+        if self.flush_interval_minutes == 0 {
+            return Vec::new();
+        }
+
+        match flush::find_flushed(&self.flush_dir) {
+            Ok(files) => {
+                if !files.is_empty() {
+                    info!("found {} flushed files from previous sessions", files.len());
+                }
+                files
+            }
+            Err(e) => {
+                warn!("failed to scan {}: {}", self.flush_dir.display(), e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+fn merge_missing(target: &mut Table, example: &Table) {
+    for (key, item) in example.iter() {
+        match (target.get_mut(key), item) {
+            (None, _) => {
+                target.insert(key, item.clone());
+                if let (Some(mut target_key), Some(example_key)) =
+                    (target.key_mut(key), example.key(key))
+                {
+                    *target_key.leaf_decor_mut() = example_key.leaf_decor().clone();
+                }
+            }
+            (Some(Item::Table(target)), Item::Table(example)) => merge_missing(target, example),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn native_options_apply_the_theme() {
+        let mut config = Config::default();
+        let options = eframe::NativeOptions::from(&config);
+        assert_eq!(options.default_theme, eframe::Theme::Light);
+        assert!(!options.follow_system_theme);
+
+        config.theme = Theme::Dark;
+        let options = eframe::NativeOptions::from(&config);
+        assert_eq!(options.default_theme, eframe::Theme::Dark);
+        assert!(!options.follow_system_theme);
+
+        config.theme = Theme::System;
+        assert!(eframe::NativeOptions::from(&config).follow_system_theme);
+    }
+
+    #[test]
+    fn memory_limit_caps_the_history() {
+        let mut config = Config {
+            history_len: 10_000_000,
+            ..Config::default()
+        };
+        assert_eq!(config.max_records(), 32 * 1024 * 1024 / RECORD_SIZE);
+        assert_eq!(config.effective_history_len(), config.max_records());
+
+        config.memory_limit_mb = 0;
+        assert_eq!(config.max_records(), 1024 * 1024 / RECORD_SIZE);
+    }
+
+    #[test]
+    fn example_parses_back_into_the_defaults() {
+        let parsed: Config = toml::from_str(&Config::generate_example()).unwrap();
+        // Config has no PartialEq, the serialized forms stand in for it
+        assert_eq!(
+            toml::to_string(&parsed).unwrap(),
+            toml::to_string(&Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn merging_adds_only_missing_keys() {
+        let existing = "# my sensor\nhistory_len = 600\n\n[reconnect]\nmax_delay_secs = 30\n";
+        let merged = Config::merge_example(existing).unwrap();
+
+        assert!(
+            merged.starts_with("# my sensor\nhistory_len = 600\n"),
+            "{}",
+            merged
+        );
+        assert_eq!(merged.matches("history_len =").count(), 1);
+        assert!(merged.contains("max_delay_secs = 30"));
+        assert!(merged.contains("initial_delay_secs = 1"));
+        assert!(merged.contains("scan_duration_secs = 5"));
+        assert!(merged.contains("# alert_high_celsius = 30.0"));
+
+        let config: Config = toml::from_str(&merged).unwrap();
+        assert_eq!(config.history_len, 600);
+        assert_eq!(config.reconnect.max_delay_secs, 30);
+        // merging again finds nothing missing
+        assert_eq!(Config::merge_example(&merged).unwrap(), merged);
+    }
+}
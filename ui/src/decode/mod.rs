@@ -0,0 +1,600 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::time::SystemTime;
+
+use btleplug::api::bleuuid::uuid_from_u16;
+use btleplug::api::PeripheralProperties;
+use log::{debug, warn};
+use uuid::Uuid;
+
+/// Temperature Measurement characteristic of the Health Thermometer service
+pub const TEMPERATURE_MEASUREMENT: Uuid = uuid_from_u16(0x2a1c);
+
+/// Eddystone service, whose service data carries the TLM frames
+pub const EDDYSTONE_SERVICE: Uuid = uuid_from_u16(0xfeaa);
+
+/// A decoded reading
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct TemperatureMeasurement {
+    pub celsius: f32,
+    /// Position in the session, counting up from 0 and wrapping at 255
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub sequence_id: Option<u8>,
+    /// When the notification was decoded
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub received_at: Option<SystemTime>,
+}
+
+impl TemperatureMeasurement {
+    /// `received_at` in the local timezone
+    #[cfg(feature = "chrono")]
+    #[must_use]
+    pub fn to_local_datetime(&self) -> Option<chrono::DateTime<chrono::Local>> {
+        self.received_at.map(chrono::DateTime::from)
+    }
+}
+
+impl From<f32> for TemperatureMeasurement {
+    fn from(celsius: f32) -> Self {
+        Self {
+            celsius,
+            sequence_id: None,
+            received_at: None,
+        }
+    }
+}
+
+/// Why a Temperature Measurement notification did not decode
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecodeError {
+    /// Notifications are exactly 5 bytes
+    WrongLength(usize),
+    /// One of the IEEE 11073 special values, NaN, NRes or ±infinity
+    ReservedNaN,
+    /// Decoded, but outside what a thermometer can read
+    OutOfRange(f32),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::WrongLength(len) => write!(f, "expected 5 bytes, got {}", len),
+            DecodeError::ReservedNaN => write!(f, "the sensor reported no valid value"),
+            DecodeError::OutOfRange(celsius) => write!(f, "{}°C is out of range", celsius),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl TemperatureMeasurement {
+    /// Bytes in a notification, flags and a 4-byte value
+    pub const LEN: usize = 5;
+    /// Readings outside this range are rejected as garbled
+    pub const RANGE_CELSIUS: RangeInclusive<f32> = -273.15..=1000.0;
+    /// Mantissas from +infinity to -infinity, NaN and NRes among them
+    const RESERVED: RangeInclusive<u32> = 0x7ffffe..=0x800002;
+}
+
+/// Decodes a GATT Temperature Measurement notification
+impl TryFrom<&[u8]> for TemperatureMeasurement {
+    type Error = DecodeError;
+
+    fn try_from(buf: &[u8]) -> Result<Self, Self::Error> {
+        let Ok(buf) = <[u8; Self::LEN]>::try_from(buf) else {
+            return Err(DecodeError::WrongLength(buf.len()));
+        };
+        let is_fahrenheit = buf[0] == 1;
+
+        let value = u32::from_le_bytes([buf[1], buf[2], buf[3], 0]);
+        debug!("temp: {}", value);
+        if Self::RESERVED.contains(&value) {
+            return Err(DecodeError::ReservedNaN);
+        }
+
+        let mut value = value as f32 / 1000.0;
+        if is_fahrenheit {
+            value = (value - 32.0) / 1.8;
+        }
+        if !Self::RANGE_CELSIUS.contains(&value) {
+            return Err(DecodeError::OutOfRange(value));
+        }
+
+        Ok(Self::from(value))
+    }
+}
+
+impl fmt::Display for TemperatureMeasurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1}°C", self.celsius)
+    }
+}
+
+/// Ordered by `f32::total_cmp`, so NaN sorts deterministically and -0.0 is
+/// below 0.0
+impl Ord for TemperatureMeasurement {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.celsius.total_cmp(&other.celsius)
+    }
+}
+
+impl PartialOrd for TemperatureMeasurement {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TemperatureMeasurement {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TemperatureMeasurement {}
+
+impl From<TemperatureMeasurement> for f32 {
+    fn from(measurement: TemperatureMeasurement) -> f32 {
+        measurement.celsius
+    }
+}
+
+impl From<TemperatureMeasurement> for f64 {
+    fn from(measurement: TemperatureMeasurement) -> f64 {
+        measurement.celsius.into()
+    }
+}
+
+/// Decoders are moved into and shared between sensor tasks, hence `Send + Sync`
+pub trait TemperatureDecoder: Send + Sync {
+    /// Celsius from a notification or advertisement payload
+    #[must_use = "decoded temperature should be sent over the channel or logged"]
+    fn decode(&self, buf: &[u8]) -> Option<f32>;
+}
+
+impl<D: TemperatureDecoder + ?Sized> TemperatureDecoder for Box<D> {
+    fn decode(&self, buf: &[u8]) -> Option<f32> {
+        (**self).decode(buf)
+    }
+}
+
+/// Connection session a notification was received in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeContext {
+    pub session_id: u64,
+}
+
+/// Numbers the readings of a connection session and rejects notifications
+/// received in another one, which some BLE stacks deliver after a reconnect
+pub struct SessionDecoder<D> {
+    decoder: D,
+    session_id: u64,
+    next_sequence_id: u8,
+}
+
+impl<D: TemperatureDecoder> SessionDecoder<D> {
+    #[must_use]
+    pub fn new(decoder: D, session_id: u64) -> Self {
+        Self {
+            decoder,
+            session_id,
+            next_sequence_id: 0,
+        }
+    }
+
+    /// Start over for a new session, numbering readings from 0 again
+    pub fn reset(&mut self, session_id: u64) {
+        self.session_id = session_id;
+        self.next_sequence_id = 0;
+    }
+
+    #[must_use = "decoded temperature should be sent over the channel or logged"]
+    pub fn decode(&mut self, buf: &[u8], context: DecodeContext) -> Option<TemperatureMeasurement> {
+        if context.session_id != self.session_id {
+            warn!(
+                "dropping stale notification of session {} in session {}",
+                context.session_id, self.session_id
+            );
+            return None;
+        }
+
+        let celsius = self.decoder.decode(buf)?;
+        let sequence_id = self.next_sequence_id;
+        self.next_sequence_id = sequence_id.wrapping_add(1);
+        Some(TemperatureMeasurement {
+            celsius,
+            sequence_id: Some(sequence_id),
+            received_at: Some(SystemTime::now()),
+        })
+    }
+}
+
+/// GATT Temperature Measurement notifications
+pub struct HealthThermometerDecoder;
+
+/// `TemperatureMeasurement::try_from` for the sensor task, logging why a
+/// notification was dropped
+impl TemperatureDecoder for HealthThermometerDecoder {
+    fn decode(&self, buf: &[u8]) -> Option<f32> {
+        match TemperatureMeasurement::try_from(buf) {
+            Ok(measurement) => Some(measurement.celsius),
+            Err(e) => {
+                debug!("dropping notification: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Unencrypted Eddystone-TLM frames from the advertisement service data
+pub struct EddystoneTlmDecoder;
+
+impl EddystoneTlmDecoder {
+    const FRAME_TYPE: u8 = 0x20;
+
+    fn is_tlm(properties: &PeripheralProperties) -> bool {
+        properties
+            .service_data
+            .get(&EDDYSTONE_SERVICE)
+            .is_some_and(|data| data.first() == Some(&Self::FRAME_TYPE))
+    }
+}
+
+impl TemperatureDecoder for EddystoneTlmDecoder {
+    fn decode(&self, buf: &[u8]) -> Option<f32> {
+        // btleplug strips the 16-bit service UUID, so bytes 6-7 of the
+        // advertised service data are at 4-5 here
+        if buf.len() < 6 || buf[0] != Self::FRAME_TYPE {
+            return None;
+        }
+
+        let value = i16::from_be_bytes([buf[4], buf[5]]);
+        // 0x8000 means the beacon has no temperature sensor
+        if value == i16::MIN {
+            return None;
+        }
+
+        Some(value as f32 / 256.0)
+    }
+}
+
+/// Govee H5075 manufacturer data, carrying temperature and humidity
+pub struct GoveeH5075Decoder;
+
+impl GoveeH5075Decoder {
+    pub const COMPANY_ID: u16 = 0xec88;
+
+    /// `(celsius, relative humidity %)` from the 6-byte payload
+    #[must_use]
+    pub fn decode_with_humidity(&self, buf: &[u8]) -> Option<(f32, f32)> {
+        if buf.len() != 6 {
+            return None;
+        }
+
+        // decimal TTTHHH, the top bit marks a negative temperature
+        let raw = u32::from_be_bytes([0, buf[1], buf[2], buf[3]]);
+        let negative = raw & 0x800000 != 0;
+        let value = raw & 0x7fffff;
+
+        let celsius = (value / 1000) as f32 / 10.0;
+        let humidity = (value % 1000) as f32 / 10.0;
+        Some((if negative { -celsius } else { celsius }, humidity))
+    }
+}
+
+impl TemperatureDecoder for GoveeH5075Decoder {
+    fn decode(&self, buf: &[u8]) -> Option<f32> {
+        self.decode_with_humidity(buf).map(|(celsius, _)| celsius)
+    }
+}
+
+/// Xiaomi Mi Flora service data
+pub struct MiFloraDecoder;
+
+impl MiFloraDecoder {
+    pub const SERVICE: Uuid = uuid_from_u16(0xfe95);
+    const NAME_PREFIX: &'static str = "Flower care";
+    /// The temperature ends at byte 13 of the 19-byte frame
+    const MIN_LEN: usize = 13;
+
+    fn is_flora(properties: &PeripheralProperties) -> bool {
+        properties.service_data.contains_key(&Self::SERVICE)
+            || properties
+                .local_name
+                .as_ref()
+                .is_some_and(|name| name.starts_with(Self::NAME_PREFIX))
+    }
+}
+
+impl TemperatureDecoder for MiFloraDecoder {
+    fn decode(&self, buf: &[u8]) -> Option<f32> {
+        if buf.len() < Self::MIN_LEN {
+            return None;
+        }
+        let value = i16::from_le_bytes([buf[11], buf[12]]);
+        Some(value as f32 / 10.0)
+    }
+}
+
+/// Advertisement field a decoder reads its payload from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AdvertisedData {
+    Service(Uuid),
+    Manufacturer(u16),
+}
+
+impl AdvertisedData {
+    pub fn payload(self, properties: &PeripheralProperties) -> Option<&[u8]> {
+        match self {
+            AdvertisedData::Service(uuid) => properties.service_data.get(&uuid),
+            AdvertisedData::Manufacturer(id) => properties.manufacturer_data.get(&id),
+        }
+        .map(Vec::as_slice)
+    }
+}
+
+/// Payload formats readings can be decoded from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+#[non_exhaustive]
+pub enum DecoderKind {
+    HealthThermometer,
+    EddystoneTlm,
+    #[value(name = "govee-h5075")]
+    GoveeH5075,
+    MiFlora,
+}
+
+impl DecoderKind {
+    /// Formats read from advertisements, in detection order
+    const ADVERTISED: [DecoderKind; 3] = [
+        DecoderKind::EddystoneTlm,
+        DecoderKind::GoveeH5075,
+        DecoderKind::MiFlora,
+    ];
+
+    /// Guess the format from what the peripheral advertises
+    #[must_use]
+    pub fn detect(properties: &PeripheralProperties) -> Self {
+        Self::ADVERTISED
+            .into_iter()
+            .find(|kind| kind.is_advertised(properties))
+            .unwrap_or(DecoderKind::HealthThermometer)
+    }
+
+    /// Whether `properties` carry a payload of this format
+    #[must_use]
+    pub fn is_advertised(self, properties: &PeripheralProperties) -> bool {
+        match self {
+            DecoderKind::EddystoneTlm => EddystoneTlmDecoder::is_tlm(properties),
+            DecoderKind::MiFlora => MiFloraDecoder::is_flora(properties),
+            _ => self
+                .advertised_data()
+                .is_some_and(|data| data.payload(properties).is_some()),
+        }
+    }
+
+    #[must_use]
+    pub fn decoder(self) -> Box<dyn TemperatureDecoder> {
+        match self {
+            DecoderKind::HealthThermometer => Box::new(HealthThermometerDecoder),
+            DecoderKind::EddystoneTlm => Box::new(EddystoneTlmDecoder),
+            DecoderKind::GoveeH5075 => Box::new(GoveeH5075Decoder),
+            DecoderKind::MiFlora => Box::new(MiFloraDecoder),
+        }
+    }
+
+    /// Where the payload is advertised, `None` for GATT formats
+    #[must_use]
+    pub fn advertised_data(self) -> Option<AdvertisedData> {
+        match self {
+            DecoderKind::HealthThermometer => None,
+            DecoderKind::EddystoneTlm => Some(AdvertisedData::Service(EDDYSTONE_SERVICE)),
+            DecoderKind::GoveeH5075 => {
+                Some(AdvertisedData::Manufacturer(GoveeH5075Decoder::COMPANY_ID))
+            }
+            DecoderKind::MiFlora => Some(AdvertisedData::Service(MiFloraDecoder::SERVICE)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measurement_converts_to_celsius() {
+        let measurement = TemperatureMeasurement::from(23.45);
+        assert_eq!(f32::from(measurement), 23.45);
+        assert_eq!(f64::from(measurement), 23.45f32 as f64);
+
+        let readings = [measurement, TemperatureMeasurement::from(-4.5)];
+        assert_eq!(readings.map(f32::from), [23.45, -4.5]);
+    }
+
+    #[test]
+    fn measurements_are_ordered_by_celsius() {
+        let readings: Vec<TemperatureMeasurement> = [21.5, -3.0, 30.25, 0.0]
+            .into_iter()
+            .map(TemperatureMeasurement::from)
+            .collect();
+        assert_eq!(readings.iter().min().unwrap().celsius, -3.0);
+        assert_eq!(readings.iter().max().unwrap().celsius, 30.25);
+    }
+
+    #[test]
+    fn nan_measurements_have_a_total_order() {
+        let nan = TemperatureMeasurement::from(f32::NAN);
+        let warm = TemperatureMeasurement::from(25.0);
+        assert_eq!(nan, nan);
+        assert!(warm < nan);
+        assert_eq!([warm, nan].iter().max(), Some(&nan));
+    }
+
+    /// Temperature Measurement notification of the mantissa `value`
+    fn notification(value: u32, fahrenheit: bool) -> Vec<u8> {
+        let mut payload = vec![fahrenheit as u8];
+        payload.extend_from_slice(&value.to_le_bytes()[..3]);
+        payload.push(0xfd);
+        payload
+    }
+
+    #[test]
+    fn notifications_decode_into_measurements() {
+        let measurement = TemperatureMeasurement::try_from(&notification(23_400, false)[..]);
+        assert_eq!(measurement.map(f32::from), Ok(23.4));
+        // 98.6°F
+        let celsius = TemperatureMeasurement::try_from(&notification(98_600, true)[..])
+            .unwrap()
+            .celsius;
+        assert!((celsius - 37.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn notifications_of_the_wrong_length_are_rejected() {
+        for len in [0, 4, 6] {
+            assert_eq!(
+                TemperatureMeasurement::try_from(&vec![0; len][..]),
+                Err(DecodeError::WrongLength(len))
+            );
+        }
+    }
+
+    #[test]
+    fn reserved_values_are_rejected() {
+        // NaN, NRes, +INF and -INF
+        for value in [0x7fffff, 0x800000, 0x7ffffe, 0x800002] {
+            assert_eq!(
+                TemperatureMeasurement::try_from(&notification(value, false)[..]),
+                Err(DecodeError::ReservedNaN)
+            );
+        }
+    }
+
+    #[test]
+    fn implausible_temperatures_are_out_of_range() {
+        assert_eq!(
+            TemperatureMeasurement::try_from(&notification(1_500_000, false)[..]),
+            Err(DecodeError::OutOfRange(1500.0))
+        );
+        assert!(HealthThermometerDecoder
+            .decode(&notification(1_500_000, false))
+            .is_none());
+    }
+
+    fn assert_send_sync<T: Send + Sync + ?Sized>() {}
+
+    #[test]
+    fn decoders_are_send_and_sync() {
+        assert_send_sync::<HealthThermometerDecoder>();
+        assert_send_sync::<EddystoneTlmDecoder>();
+        assert_send_sync::<GoveeH5075Decoder>();
+        assert_send_sync::<MiFloraDecoder>();
+        assert_send_sync::<SessionDecoder<HealthThermometerDecoder>>();
+        assert_send_sync::<Box<dyn TemperatureDecoder>>();
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn decoded_readings_carry_their_local_time() {
+        let payload = [0x00, 0x68, 0x5b, 0x00, 0x00];
+        assert!(TemperatureMeasurement::from(23.4)
+            .to_local_datetime()
+            .is_none());
+
+        let before = chrono::Local::now();
+        let reading = SessionDecoder::new(HealthThermometerDecoder, 1)
+            .decode(&payload, DecodeContext { session_id: 1 })
+            .unwrap();
+        let at = reading.to_local_datetime().unwrap();
+        assert!(before <= at && at <= chrono::Local::now());
+    }
+
+    #[test]
+    fn session_decoder_rejects_stale_notifications() {
+        // 23.4°C, exactly 5 bytes like any valid notification
+        let payload = [0x00, 0x68, 0x5b, 0x00, 0x00];
+        let (first, second) = (
+            DecodeContext { session_id: 1 },
+            DecodeContext { session_id: 2 },
+        );
+
+        let mut decoder = SessionDecoder::new(HealthThermometerDecoder, 1);
+        let reading = decoder.decode(&payload, first).unwrap();
+        assert_eq!((reading.celsius, reading.sequence_id), (23.4, Some(0)));
+        assert_eq!(
+            decoder.decode(&payload, first).unwrap().sequence_id,
+            Some(1)
+        );
+
+        // reconnected: one left over from the first session arrives
+        decoder.reset(2);
+        assert_eq!(decoder.decode(&payload, first), None);
+        let reading = decoder.decode(&payload, second).unwrap();
+        assert_eq!(reading.sequence_id, Some(0));
+    }
+
+    #[test]
+    fn govee_h5075_positive() {
+        // 0x03519f = 217503: 21.7°C, 50.3%
+        let payload = [0x00, 0x03, 0x51, 0x9f, 0x64, 0x00];
+        assert_eq!(
+            GoveeH5075Decoder.decode_with_humidity(&payload),
+            Some((21.7, 50.3))
+        );
+    }
+
+    #[test]
+    fn govee_h5075_negative() {
+        // 0x80 flag with 0x00c62d = 50733: -5.0°C, 73.3%
+        let payload = [0x00, 0x80, 0xc6, 0x2d, 0x50, 0x00];
+        assert_eq!(
+            GoveeH5075Decoder.decode_with_humidity(&payload),
+            Some((-5.0, 73.3))
+        );
+    }
+
+    #[test]
+    fn govee_h5075_wrong_length() {
+        assert_eq!(GoveeH5075Decoder.decode(&[0x00, 0x03, 0x51, 0x9f]), None);
+    }
+
+    /// Advertisement of a "Flower care" sensor reading 22.5°C
+    const MI_FLORA_FRAME: [u8; 19] = [
+        0x71, 0x20, 0x98, 0x00, 0x5c, 0x73, 0x2f, 0x6b, 0x8d, 0x7c, 0xc4, 0xe1, 0x00, 0x0d, 0x04,
+        0x10, 0x02, 0xe1, 0x00,
+    ];
+
+    #[test]
+    fn mi_flora_detected_and_decoded() {
+        let mut properties = PeripheralProperties {
+            local_name: Some("Flower care".to_owned()),
+            ..Default::default()
+        };
+        properties
+            .service_data
+            .insert(MiFloraDecoder::SERVICE, MI_FLORA_FRAME.to_vec());
+
+        let kind = DecoderKind::detect(&properties);
+        assert_eq!(kind, DecoderKind::MiFlora);
+
+        let payload = kind
+            .advertised_data()
+            .and_then(|data| data.payload(&properties))
+            .unwrap();
+        assert_eq!(kind.decoder().decode(payload), Some(22.5));
+    }
+
+    #[test]
+    fn mi_flora_too_short() {
+        assert_eq!(MiFloraDecoder.decode(&MI_FLORA_FRAME[..12]), None);
+    }
+}
@@ -0,0 +1,177 @@
+//! Configuration from environment variables, for deployments where a config
+//! file is awkward. They override the config file and are overridden by the
+//! command line. An empty value unsets the keys marked optional.
+//!
+//! | Variable                                | Config key                        |
+//! |-----------------------------------------|-----------------------------------|
+//! | `BLE_TEMP_SENSOR_FILTER`                | `sensor_name_filter`              |
+//! | `BLE_TEMP_HISTORY_LEN`                  | `history_len`                     |
+//! | `BLE_TEMP_MEMORY_LIMIT_MB`              | `memory_limit_mb`                 |
+//! | `BLE_TEMP_FLUSH_INTERVAL_MINUTES`       | `flush_interval_minutes`          |
+//! | `BLE_TEMP_FLUSH_DIR`                    | `flush_dir`                       |
+//! | `BLE_TEMP_ALERT_HIGH`                   | `alert_high_celsius`, optional    |
+//! | `BLE_TEMP_ALERT_LOW`                    | `alert_low_celsius`, optional     |
+//! | `BLE_TEMP_ALERT_HYSTERESIS`             | `alert_hysteresis_celsius`        |
+//! | `BLE_TEMP_SCAN_DURATION_SECS`           | `scan_duration_secs`              |
+//! | `BLE_TEMP_CONNECT_TIMEOUT_SECS`         | `connect_timeout_secs`            |
+//! | `BLE_TEMP_MAX_SCAN_RETRIES`             | `max_scan_retries`                |
+//! | `BLE_TEMP_CALIBRATION_OFFSET`           | `calibration_offset`              |
+//! | `BLE_TEMP_DISPLAY_UNIT`                 | `display_unit`                    |
+//! | `BLE_TEMP_RECONNECT_INITIAL_DELAY_SECS` | `reconnect.initial_delay_secs`    |
+//! | `BLE_TEMP_RECONNECT_MAX_DELAY_SECS`     | `reconnect.max_delay_secs`        |
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::config::Config;
+
+/// Override `config` with the variables set in the environment
+pub fn apply(config: &mut Config) -> Result<(), String> {
+    apply_from(config, |name| std::env::var(name).ok())
+}
+
+fn apply_from(config: &mut Config, var: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+    let var = &var;
+    set(
+        var,
+        "BLE_TEMP_SENSOR_FILTER",
+        &mut config.sensor_name_filter,
+    )?;
+    set(var, "BLE_TEMP_HISTORY_LEN", &mut config.history_len)?;
+    set(var, "BLE_TEMP_MEMORY_LIMIT_MB", &mut config.memory_limit_mb)?;
+    set(
+        var,
+        "BLE_TEMP_FLUSH_INTERVAL_MINUTES",
+        &mut config.flush_interval_minutes,
+    )?;
+    set(var, "BLE_TEMP_FLUSH_DIR", &mut config.flush_dir)?;
+    set_optional(var, "BLE_TEMP_ALERT_HIGH", &mut config.alert_high_celsius)?;
+    set_optional(var, "BLE_TEMP_ALERT_LOW", &mut config.alert_low_celsius)?;
+    set(
+        var,
+        "BLE_TEMP_ALERT_HYSTERESIS",
+        &mut config.alert_hysteresis_celsius,
+    )?;
+    set(
+        var,
+        "BLE_TEMP_SCAN_DURATION_SECS",
+        &mut config.scan_duration_secs,
+    )?;
+    set(
+        var,
+        "BLE_TEMP_CONNECT_TIMEOUT_SECS",
+        &mut config.connect_timeout_secs,
+    )?;
+    set(
+        var,
+        "BLE_TEMP_MAX_SCAN_RETRIES",
+        &mut config.max_scan_retries,
+    )?;
+    set(
+        var,
+        "BLE_TEMP_CALIBRATION_OFFSET",
+        &mut config.calibration_offset,
+    )?;
+    set(var, "BLE_TEMP_DISPLAY_UNIT", &mut config.display_unit)?;
+    set(
+        var,
+        "BLE_TEMP_RECONNECT_INITIAL_DELAY_SECS",
+        &mut config.reconnect.initial_delay_secs,
+    )?;
+    set(
+        var,
+        "BLE_TEMP_RECONNECT_MAX_DELAY_SECS",
+        &mut config.reconnect.max_delay_secs,
+    )
+}
+
+fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|e| format!("invalid {}={:?}: {}", name, value, e))
+}
+
+fn set<T: FromStr>(
+    var: impl Fn(&str) -> Option<String>,
+    name: &str,
+    field: &mut T,
+) -> Result<(), String>
+where
+    T::Err: Display,
+{
+    if let Some(value) = var(name) {
+        *field = parse(name, &value)?;
+    }
+    Ok(())
+}
+
+fn set_optional<T: FromStr>(
+    var: impl Fn(&str) -> Option<String>,
+    name: &str,
+    field: &mut Option<T>,
+) -> Result<(), String>
+where
+    T::Err: Display,
+{
+    match var(name).as_deref() {
+        None => {}
+        Some("") => *field = None,
+        Some(value) => *field = Some(parse(name, value)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::unit::TemperatureUnit;
+
+    fn apply_vars(config: &mut Config, vars: &[(&str, &str)]) -> Result<(), String> {
+        let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+        apply_from(config, |name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn variables_override_the_config() {
+        let mut config = Config {
+            history_len: 600,
+            alert_low_celsius: Some(15.0),
+            ..Config::default()
+        };
+        apply_vars(
+            &mut config,
+            &[
+                ("BLE_TEMP_SENSOR_FILTER", "Kitchen"),
+                ("BLE_TEMP_ALERT_HIGH", "28.5"),
+                ("BLE_TEMP_ALERT_LOW", ""),
+                ("BLE_TEMP_DISPLAY_UNIT", "f"),
+                ("BLE_TEMP_RECONNECT_MAX_DELAY_SECS", "30"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.sensor_name_filter, "Kitchen");
+        // unset variables keep the file's value
+        assert_eq!(config.history_len, 600);
+        assert_eq!(config.alert_high_celsius, Some(28.5));
+        assert_eq!(config.alert_low_celsius, None);
+        assert_eq!(config.display_unit, TemperatureUnit::Fahrenheit);
+        assert_eq!(config.reconnect.max_delay_secs, 30);
+    }
+
+    #[test]
+    fn invalid_values_name_the_variable() {
+        let e =
+            apply_vars(&mut Config::default(), &[("BLE_TEMP_HISTORY_LEN", "many")]).unwrap_err();
+        assert!(
+            e.starts_with("invalid BLE_TEMP_HISTORY_LEN=\"many\""),
+            "{}",
+            e
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+
+/// Failures of the sensor task, worded for the error banner
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum BleTemperatureError {
+    NoAdapter,
+    SensorNotFound,
+    CharacteristicNotFound,
+    ConnectTimeout,
+    /// `--max-reconnects` attempts in a row failed to connect
+    TooManyReconnects(u32),
+    /// `--timeout-on-idle` seconds passed without finding the sensor
+    IdleTimeout(u64),
+    /// The OS refused access to Bluetooth
+    PermissionDenied,
+    Ble(btleplug::Error),
+}
+
+impl fmt::Display for BleTemperatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BleTemperatureError::NoAdapter => write!(f, "no Bluetooth adapter found"),
+            BleTemperatureError::SensorNotFound => {
+                write!(
+                    f,
+                    "no matching sensor found, is it powered on and in range?"
+                )
+            }
+            BleTemperatureError::CharacteristicNotFound => {
+                write!(
+                    f,
+                    "the sensor has no temperature measurement characteristic"
+                )
+            }
+            BleTemperatureError::ConnectTimeout => write!(f, "timed out connecting to the sensor"),
+            BleTemperatureError::TooManyReconnects(n) => {
+                write!(f, "gave up after {} reconnect attempts", n)
+            }
+            BleTemperatureError::IdleTimeout(secs) => {
+                write!(f, "No sensor found within {}s, exiting", secs)
+            }
+            BleTemperatureError::PermissionDenied => write!(f, "{}", PERMISSION_REMEDY),
+            BleTemperatureError::Ble(e) => write!(f, "Bluetooth error: {}", e),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+const PERMISSION_REMEDY: &str =
+    "Permission denied: add your user to the 'bluetooth' group: sudo usermod -aG bluetooth $USER";
+#[cfg(target_os = "macos")]
+const PERMISSION_REMEDY: &str = "Permission denied: allow Bluetooth for this app in System \
+    Preferences > Security & Privacy > Privacy > Bluetooth";
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+const PERMISSION_REMEDY: &str = "Permission denied: allow this app to use Bluetooth";
+
+/// D-Bus errors BlueZ and the system bus answer unprivileged callers with
+const DBUS_PERMISSION_ERRORS: [&str; 3] = [
+    "org.freedesktop.DBus.Error.AccessDenied",
+    "org.bluez.Error.NotPermitted",
+    "org.bluez.Error.NotAuthorized",
+];
+
+/// btleplug only reports `PermissionDenied` on some platforms, BlueZ failures
+/// arrive as D-Bus or OS errors inside `Other`
+fn is_permission_denied(e: &btleplug::Error) -> bool {
+    let btleplug::Error::Other(inner) = e else {
+        return matches!(e, btleplug::Error::PermissionDenied);
+    };
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(inner.as_ref());
+    while let Some(e) = source {
+        if let Some(io) = e.downcast_ref::<std::io::Error>() {
+            if io.kind() == std::io::ErrorKind::PermissionDenied {
+                return true;
+            }
+        }
+        let message = e.to_string();
+        if DBUS_PERMISSION_ERRORS
+            .iter()
+            .any(|name| message.contains(name))
+        {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+impl std::error::Error for BleTemperatureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BleTemperatureError::Ble(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<btleplug::Error> for BleTemperatureError {
+    fn from(e: btleplug::Error) -> Self {
+        if is_permission_denied(&e) {
+            return BleTemperatureError::PermissionDenied;
+        }
+        BleTemperatureError::Ble(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permission_errors_are_recognized() {
+        let denied = |e: btleplug::Error| {
+            matches!(
+                BleTemperatureError::from(e),
+                BleTemperatureError::PermissionDenied
+            )
+        };
+        assert!(denied(btleplug::Error::PermissionDenied));
+        // EACCES
+        let eacces = std::io::Error::from_raw_os_error(13);
+        assert!(denied(btleplug::Error::Other(Box::new(eacces))));
+        let dbus = "D-Bus error org.bluez.Error.NotPermitted: Operation not permitted";
+        assert!(denied(btleplug::Error::Other(dbus.into())));
+        assert!(!denied(btleplug::Error::DeviceNotFound));
+    }
+}
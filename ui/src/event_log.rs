@@ -0,0 +1,125 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::SystemTime;
+
+use crate::alarm::AlarmKind;
+use crate::clock;
+
+/// Oldest events are dropped beyond this many entries
+const MAX_EVENTS: usize = 500;
+
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum LogEvent {
+    Connected { name: String, address: String },
+    Disconnected,
+    ScanStarted,
+    DecodeFailed { raw: Vec<u8> },
+    AlertFired { celsius: f32, kind: AlarmKind },
+    ReconnectAttempt { n: u32 },
+    ReadingReceived { celsius: f32 },
+}
+
+impl fmt::Display for LogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LogEvent::Connected { name, address } => {
+                write!(f, "connected to {} ({})", name, address)
+            }
+            LogEvent::Disconnected => write!(f, "disconnected"),
+            LogEvent::ScanStarted => write!(f, "scan started"),
+            LogEvent::DecodeFailed { raw } => {
+                write!(f, "decode failed:")?;
+                raw.iter().try_for_each(|b| write!(f, " {:02x}", b))
+            }
+            LogEvent::AlertFired { celsius, kind } => {
+                let kind = match kind {
+                    AlarmKind::High => "high",
+                    AlarmKind::Low => "low",
+                };
+                write!(f, "{} alert at {:.1}°C", kind, celsius)
+            }
+            LogEvent::ReconnectAttempt { n } => write!(f, "reconnect attempt {}", n),
+            LogEvent::ReadingReceived { celsius } => write!(f, "reading {:.2}°C", celsius),
+        }
+    }
+}
+
+/// Timestamped BLE lifecycle and alert events of the session
+#[derive(Default)]
+pub struct EventLog {
+    pub events: VecDeque<(SystemTime, LogEvent)>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, event: LogEvent) {
+        if self.events.len() >= MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back((SystemTime::now(), event));
+    }
+
+    /// One `HH:MM:SS.mmm event` line per entry
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.events.iter().map(|(at, event)| {
+            let at = clock::format(*at, "%H:%M:%S%.3f");
+            format!("{} {}", at, event)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oldest_events_are_dropped() {
+        let mut log = EventLog::default();
+        for n in 0..MAX_EVENTS as u32 + 20 {
+            log.push(LogEvent::ReconnectAttempt { n });
+        }
+        assert_eq!(log.events.len(), MAX_EVENTS);
+        assert!(matches!(
+            log.events.front(),
+            Some((_, LogEvent::ReconnectAttempt { n: 20 }))
+        ));
+
+        let lines: Vec<String> = log.lines().collect();
+        assert_eq!(lines.len(), MAX_EVENTS);
+        assert!(lines[0].ends_with(" reconnect attempt 20"), "{}", lines[0]);
+        assert!(lines[MAX_EVENTS - 1].ends_with(" reconnect attempt 519"));
+    }
+
+    #[test]
+    fn events_are_formatted() {
+        let cases = [
+            (
+                LogEvent::Connected {
+                    name: "Temperature01".into(),
+                    address: "AA:BB:CC:DD:EE:FF".into(),
+                },
+                "connected to Temperature01 (AA:BB:CC:DD:EE:FF)",
+            ),
+            (
+                LogEvent::DecodeFailed {
+                    raw: vec![0x00, 0x0a, 0xff],
+                },
+                "decode failed: 00 0a ff",
+            ),
+            (
+                LogEvent::AlertFired {
+                    celsius: 31.25,
+                    kind: AlarmKind::High,
+                },
+                "high alert at 31.2°C",
+            ),
+            (
+                LogEvent::ReadingReceived { celsius: 21.5 },
+                "reading 21.50°C",
+            ),
+        ];
+        for (event, expected) in cases {
+            assert_eq!(event.to_string(), expected);
+        }
+    }
+}
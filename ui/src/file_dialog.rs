@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use eframe::egui;
+
+/// Picks files with the native dialog when built with the `file-dialog`
+/// feature, otherwise with a minimal "enter a path" window
+pub struct FileDialog {
+    title: &'static str,
+    open: bool,
+    path: String,
+    /// Native dialog result, handed out by the next `show`
+    picked: Option<PathBuf>,
+}
+
+impl FileDialog {
+    pub fn new(title: &'static str) -> Self {
+        Self {
+            title,
+            open: false,
+            path: String::new(),
+            picked: None,
+        }
+    }
+
+    #[cfg(feature = "file-dialog")]
+    pub fn open(&mut self) {
+        // blocks this frame until the dialog closes, like a modal would
+        self.picked = rfd::FileDialog::new().set_title(self.title).pick_file();
+    }
+
+    #[cfg(not(feature = "file-dialog"))]
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    /// Draw the window if open, returning the path once confirmed
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<PathBuf> {
+        if self.picked.is_some() {
+            return self.picked.take();
+        }
+        if !self.open {
+            return None;
+        }
+
+        let mut picked = None;
+        let mut open = self.open;
+        egui::Window::new(self.title)
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Path:");
+                    let edit = ui.text_edit_singleline(&mut self.path);
+                    let entered =
+                        edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if (ui.button("Confirm").clicked() || entered) && !self.path.is_empty() {
+                        picked = Some(PathBuf::from(&self.path));
+                    }
+                });
+            });
+
+        self.open = open && picked.is_none();
+        picked
+    }
+}
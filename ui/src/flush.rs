@@ -0,0 +1,164 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{error, info};
+
+use crate::history::TemperatureHistory;
+
+/// Periodically moves new readings into `readings_<session>_<index>.csv.gz`
+/// files, keeping only the most recent ones in memory for the chart
+pub struct Flusher {
+    dir: PathBuf,
+    interval: Duration,
+    /// Span of flushed readings, ending at the newest, left in the history
+    keep: Duration,
+    session_start: u64,
+    index: usize,
+    last_flush: Instant,
+    /// Time of the newest reading written so far
+    flushed_until: Option<Instant>,
+}
+
+impl Flusher {
+    pub fn new(dir: PathBuf, interval: Duration) -> Self {
+        let session_start = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        Self {
+            dir,
+            interval,
+            keep: interval,
+            session_start,
+            index: 0,
+            last_flush: Instant::now(),
+            flushed_until: None,
+        }
+    }
+
+    /// Write the readings of `history` newer than the last flush to a new file
+    /// once the flush interval has elapsed, using `label` as the header of the
+    /// temperature column. Flushed readings older than one interval before the
+    /// newest are then removed from `history` to free memory
+    pub fn poll(&mut self, history: &mut TemperatureHistory, label: &str) {
+        if self.last_flush.elapsed() < self.interval {
+            return;
+        }
+        self.last_flush = Instant::now();
+
+        let readings: Vec<(Instant, f32)> = history.readings_after(self.flushed_until).collect();
+        let Some(&(newest, _)) = readings.last() else {
+            return;
+        };
+        self.flushed_until = Some(newest);
+        if let Some(before) = newest.checked_sub(self.keep) {
+            history.remove_before(before);
+        }
+
+        let now = (Instant::now(), SystemTime::now());
+        let rows: Vec<(u128, f32)> = readings
+            .into_iter()
+            .map(|(t, celsius)| (unix_ms(now, t), celsius))
+            .collect();
+
+        let path = self.dir.join(format!(
+            "readings_{}_{}.csv.gz",
+            self.session_start, self.index
+        ));
+        self.index += 1;
+
+        let label = label.to_owned();
+        tokio::task::spawn_blocking(move || match write_csv_gz(&path, &label, &rows) {
+            Ok(()) => info!("flushed {} readings to {}", rows.len(), path.display()),
+            Err(e) => error!("failed to flush readings to {}: {}", path.display(), e),
+        });
+    }
+}
+
+/// List previously flushed files in `dir`
+pub fn find_flushed(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("readings_") && name.ends_with(".csv.gz"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Wall-clock time of `t`, given a matching `(Instant, SystemTime)` pair
+pub fn unix_ms(now: (Instant, SystemTime), t: Instant) -> u128 {
+    (now.1 - now.0.duration_since(t))
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+fn write_csv_gz(path: &Path, label: &str, rows: &[(u128, f32)]) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(GzEncoder::new(file, Compression::default()));
+
+    writeln!(writer, "timestamp_unix_ms,{}", label)?;
+    for (ts, celsius) in rows {
+        writeln!(writer, "{},{}", ts, celsius)?;
+    }
+
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .finish()?
+        .sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::overlay::Overlay;
+
+    #[test]
+    fn flushed_files_load_as_overlays() {
+        let dir =
+            std::env::temp_dir().join(format!("ble-temperature-flush-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let start = Instant::now().checked_sub(Duration::from_secs(60)).unwrap();
+        let mut history = TemperatureHistory::with_capacity(10);
+        let mut flusher = Flusher::new(dir.clone(), Duration::ZERO);
+        flusher.keep = Duration::from_secs(5);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            for i in 0..3 {
+                history.push(start + Duration::from_secs(i), 20.0 + i as f32);
+            }
+            flusher.poll(&mut history, "celsius");
+            // all within the kept span
+            assert_eq!(history.len(), 3);
+            history.push(start + Duration::from_secs(10), 25.0);
+            flusher.poll(&mut history, "celsius");
+            // nothing new
+            flusher.poll(&mut history, "celsius");
+        });
+        // waits for the writes
+        runtime.shutdown_timeout(Duration::from_secs(10));
+
+        let files = find_flushed(&dir).unwrap();
+        let overlays: Vec<Overlay> = files.iter().map(|f| Overlay::load(f).unwrap()).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(overlays[0].points, [[0.0, 20.0], [1.0, 21.0], [2.0, 22.0]]);
+        assert_eq!(overlays[1].points, [[0.0, 25.0]]);
+        // the first three were flushed and fell out of the kept span
+        let kept: Vec<f32> = history.iter().map(|&(_, c)| c).collect();
+        assert_eq!(kept, [25.0]);
+    }
+}
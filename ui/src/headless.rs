@@ -0,0 +1,100 @@
+use std::io;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+use tokio::runtime::Handle;
+use tokio::time::MissedTickBehavior;
+
+use crate::ble::Event;
+use crate::sink::OutputSink;
+use crate::stats::TemperatureStats;
+
+/// Write each reading to `sink` until the sensor task drops its sender
+pub fn record(
+    rx: Receiver<Event>,
+    mut sink: Box<dyn OutputSink + Send>,
+) -> io::Result<TemperatureStats> {
+    let mut stats = TemperatureStats::default();
+
+    for event in rx {
+        if let Event::Reading(reading) = event {
+            stats += reading.celsius;
+            sink.write_reading(&reading)?;
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Statistics of the first `count` readings, or of fewer if the sensor task
+/// drops its sender before
+pub fn collect(rx: Receiver<Event>, count: u64) -> TemperatureStats {
+    let mut stats = TemperatureStats::default();
+    for event in rx {
+        if let Event::Reading(reading) = event {
+            stats += reading.celsius;
+            if stats.count >= count {
+                break;
+            }
+        }
+    }
+    stats
+}
+
+/// Write the newest reading of each `interval` to `sink`, or a missing
+/// reading if none arrived, until the sensor task drops its sender. The
+/// ticks run on the runtime of `handle`
+pub fn record_every(
+    rx: Receiver<Event>,
+    mut sink: Box<dyn OutputSink + Send>,
+    interval: Duration,
+    handle: Handle,
+) -> io::Result<TemperatureStats> {
+    let mut stats = TemperatureStats::default();
+    let mut ticks = handle.block_on(async {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        // the first tick completes immediately
+        ticks.tick().await;
+        ticks
+    });
+
+    loop {
+        handle.block_on(ticks.tick());
+
+        let mut latest = None;
+        let closed = loop {
+            match rx.try_recv() {
+                Ok(Event::Reading(reading)) => {
+                    stats += reading.celsius;
+                    latest = Some(reading);
+                }
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+
+        match latest {
+            Some(reading) => sink.write_reading(&reading)?,
+            None if !closed => sink.write_missing()?,
+            None => {}
+        }
+        if closed {
+            return Ok(stats);
+        }
+    }
+}
+
+/// `{"min": 22.1, "max": 24.3, "mean": 23.2, "std_dev": 0.4, "n": 120}`
+pub fn summary_json(stats: &TemperatureStats) -> String {
+    let field = |value: Option<f32>| value.map_or("null".to_owned(), |v| format!("{:.1}", v));
+    format!(
+        "{{\"min\": {}, \"max\": {}, \"mean\": {}, \"std_dev\": {}, \"n\": {}}}",
+        field(stats.min),
+        field(stats.max),
+        field(stats.mean()),
+        field(stats.std_dev()),
+        stats.count
+    )
+}
@@ -0,0 +1,502 @@
+use std::collections::vec_deque::{self, VecDeque};
+use std::ops::Deref;
+use std::time::{Duration, Instant};
+
+/// Bounded in-memory readings plus the all-time extremes of the session
+pub struct TemperatureHistory {
+    readings: VecDeque<(Instant, f32)>,
+    max_len: usize,
+    min: Option<f32>,
+    max: Option<f32>,
+}
+
+impl TemperatureHistory {
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            readings: VecDeque::with_capacity(capacity),
+            max_len: capacity,
+            min: None,
+            max: None,
+        }
+    }
+
+    #[must_use]
+    pub fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// Change how many readings are kept, dropping the oldest ones if shrinking
+    pub fn set_max_len(&mut self, max_len: usize) {
+        self.max_len = max_len;
+        while self.readings.len() > self.max_len {
+            self.readings.pop_front();
+        }
+    }
+
+    pub fn push(&mut self, at: Instant, celsius: f32) {
+        if self.readings.len() >= self.max_len {
+            self.readings.pop_front();
+        }
+        self.readings.push_back((at, celsius));
+
+        self.min = Some(self.min.map_or(celsius, |min| min.min(celsius)));
+        self.max = Some(self.max.map_or(celsius, |max| max.max(celsius)));
+    }
+
+    /// Lowest reading seen, including ones no longer kept in memory
+    #[must_use]
+    pub fn min(&self) -> Option<f32> {
+        self.min
+    }
+
+    /// Highest reading seen, including ones no longer kept in memory
+    #[must_use]
+    pub fn max(&self) -> Option<f32> {
+        self.max
+    }
+
+    /// Kept readings, oldest first
+    #[must_use]
+    pub fn iter(&self) -> vec_deque::Iter<'_, (Instant, f32)> {
+        self.readings.iter()
+    }
+
+    /// Kept readings taken at or after `start` and before `end`. Readings are
+    /// pushed in time order, so the start is found by binary search
+    pub fn readings_in_range(
+        &self,
+        start: Instant,
+        end: Instant,
+    ) -> impl Iterator<Item = (Instant, f32)> + '_ {
+        let first = self.readings.partition_point(|(at, _)| *at < start);
+        self.readings
+            .range(first..)
+            .take_while(move |(at, _)| *at < end)
+            .copied()
+    }
+
+    /// Kept readings taken after `after`, or all of them for `None`
+    pub fn readings_after(
+        &self,
+        after: Option<Instant>,
+    ) -> impl Iterator<Item = (Instant, f32)> + '_ {
+        let first = after.map_or(0, |after| {
+            self.readings.partition_point(|(at, _)| *at <= after)
+        });
+        self.readings.range(first..).copied()
+    }
+
+    /// Drop the kept readings taken before `before`. The extremes still cover
+    /// them
+    pub fn remove_before(&mut self, before: Instant) {
+        let first = self.readings.partition_point(|(at, _)| *at < before);
+        self.readings.drain(..first);
+    }
+
+    /// One reading every `interval` from the first kept reading up to the
+    /// last, linearly interpolated between the two readings around it.
+    /// Empty with fewer than two readings
+    #[must_use]
+    pub fn resample(&self, interval: Duration) -> Vec<(Instant, f32)> {
+        if self.readings.len() < 2 || interval.is_zero() {
+            return Vec::new();
+        }
+        let (first, _) = self.readings[0];
+        let (last, _) = self.readings[self.readings.len() - 1];
+
+        let mut resampled = Vec::new();
+        let mut at = first;
+        let mut i = 0;
+
+        while at <= last {
+            // readings[i] is the last one at or before `at`
+            while self.readings[i + 1].0 <= at && i + 2 < self.readings.len() {
+                i += 1;
+            }
+            let ((t0, c0), (t1, c1)) = (self.readings[i], self.readings[i + 1]);
+            let span = t1.duration_since(t0).as_secs_f32();
+            let celsius = if span > 0.0 {
+                c0 + (c1 - c0) * at.duration_since(t0).as_secs_f32() / span
+            } else {
+                c0
+            };
+
+            resampled.push((at, celsius));
+            at += interval;
+        }
+        resampled
+    }
+
+    /// Temperature at `target`, linearly interpolated between the two kept
+    /// readings around it. `None` before the first or after the last reading
+    #[must_use]
+    pub fn temperature_at(&self, target: Instant) -> Option<f32> {
+        let (first, _) = *self.readings.front()?;
+        let (last, last_celsius) = *self.readings.back()?;
+        if target < first || target > last {
+            return None;
+        }
+
+        // index of the first reading after `target`, at least 1
+        let next = self.readings.partition_point(|(at, _)| *at <= target);
+        if next == self.readings.len() {
+            return Some(last_celsius);
+        }
+        let ((t0, c0), (t1, c1)) = (self.readings[next - 1], self.readings[next]);
+        let span = t1.duration_since(t0).as_secs_f32();
+        Some(c0 + (c1 - c0) * target.duration_since(t0).as_secs_f32() / span)
+    }
+
+    /// Change in °C per minute over the readings of the last `window` before
+    /// the newest one, as the least squares slope. `None` without two readings
+    /// at different times in it
+    #[must_use]
+    pub fn rate_per_minute(&self, window: Duration) -> Option<f32> {
+        let (last, _) = *self.readings.back()?;
+        let start = last.checked_sub(window).unwrap_or(self.readings[0].0);
+        let first = self.readings.partition_point(|(at, _)| *at < start);
+        let recent = self.readings.range(first..);
+
+        let n = recent.len() as f64;
+        let (mut sum_t, mut sum_c, mut sum_tt, mut sum_tc) = (0.0, 0.0, 0.0, 0.0);
+        for (at, celsius) in recent {
+            let t = at.duration_since(start).as_secs_f64() / 60.0;
+            let c = *celsius as f64;
+            sum_t += t;
+            sum_c += c;
+            sum_tt += t * t;
+            sum_tc += t * c;
+        }
+
+        let denominator = n * sum_tt - sum_t * sum_t;
+        (denominator > f64::EPSILON).then(|| ((n * sum_tc - sum_t * sum_c) / denominator) as f32)
+    }
+
+    /// Readings per second, from the mean interval between the last `window`
+    /// readings. `None` with fewer than two
+    #[must_use]
+    pub fn rate_hz(&self, window: usize) -> Option<f32> {
+        let window = window.min(self.readings.len());
+        if window < 2 {
+            return None;
+        }
+        let (first, _) = self.readings[self.readings.len() - window];
+        let (last, _) = self.readings[self.readings.len() - 1];
+        let span = last.duration_since(first).as_secs_f32();
+        (span > 0.0).then(|| (window - 1) as f32 / span)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    /// Mean of the readings kept in memory
+    #[must_use]
+    pub fn mean(&self) -> Option<f32> {
+        if self.readings.is_empty() {
+            return None;
+        }
+        let sum: f64 = self.iter().map(|(_, c)| *c as f64).sum();
+        Some((sum / self.readings.len() as f64) as f32)
+    }
+
+    /// Population standard deviation of the readings kept in memory
+    #[must_use]
+    pub fn std_dev(&self) -> Option<f32> {
+        let mean = self.mean()? as f64;
+        let sum_sq: f64 = self.iter().map(|(_, c)| (*c as f64 - mean).powi(2)).sum();
+        Some((sum_sq / self.readings.len() as f64).sqrt() as f32)
+    }
+}
+
+/// The kept readings, oldest first, for building plot points and the like.
+/// Read-only, so `max_len` and the all-time extremes always hold
+impl Deref for TemperatureHistory {
+    type Target = VecDeque<(Instant, f32)>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.readings
+    }
+}
+
+impl<'a> IntoIterator for &'a TemperatureHistory {
+    type Item = &'a (Instant, f32);
+    type IntoIter = vec_deque::Iter<'a, (Instant, f32)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn history_of(capacity: usize, values: &[f32]) -> TemperatureHistory {
+        let mut history = TemperatureHistory::with_capacity(capacity);
+        let now = Instant::now();
+        for &celsius in values {
+            history.push(now, celsius);
+        }
+        history
+    }
+
+    fn secs(start: Instant, secs: u64) -> Instant {
+        start + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn readings_in_range_is_half_open() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(10);
+        for i in 0..5 {
+            history.push(secs(start, i), i as f32);
+        }
+
+        let in_range: Vec<f32> = history
+            .readings_in_range(secs(start, 1), secs(start, 3))
+            .map(|(_, c)| c)
+            .collect();
+        assert_eq!(in_range, [1.0, 2.0]);
+    }
+
+    #[test]
+    fn readings_in_range_between_readings() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(10);
+        for i in [0, 10, 20] {
+            history.push(secs(start, i), i as f32);
+        }
+
+        let in_range: Vec<(Instant, f32)> = history
+            .readings_in_range(secs(start, 5), secs(start, 25))
+            .collect();
+        assert_eq!(in_range, [(secs(start, 10), 10.0), (secs(start, 20), 20.0)]);
+        assert_eq!(
+            history
+                .readings_in_range(secs(start, 11), secs(start, 19))
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn readings_in_range_skips_dropped_readings() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(3);
+        for i in 0..6 {
+            history.push(secs(start, i), i as f32);
+        }
+
+        let in_range: Vec<f32> = history
+            .readings_in_range(start, secs(start, 100))
+            .map(|(_, c)| c)
+            .collect();
+        assert_eq!(in_range, [3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn resample_interpolates_between_readings() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(10);
+        history.push(start, 20.0);
+        history.push(secs(start, 4), 22.0);
+        history.push(secs(start, 6), 21.0);
+
+        let resampled: Vec<f32> = history
+            .resample(Duration::from_secs(1))
+            .into_iter()
+            .map(|(_, c)| c)
+            .collect();
+        assert_eq!(resampled, [20.0, 20.5, 21.0, 21.5, 22.0, 21.5, 21.0]);
+    }
+
+    #[test]
+    fn resample_timestamps_are_uniform() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(10);
+        history.push(start, 10.0);
+        history.push(start + Duration::from_millis(1050), 20.0);
+        history.push(start + Duration::from_millis(1950), 30.0);
+
+        let resampled = history.resample(Duration::from_millis(500));
+        let times: Vec<Instant> = resampled.iter().map(|(at, _)| *at).collect();
+        let expected: Vec<Instant> = (0..4)
+            .map(|i| start + Duration::from_millis(500 * i))
+            .collect();
+        assert_eq!(times, expected);
+        // 1500 ms is 450 of the 900 ms between the last two readings
+        assert!((resampled[3].1 - 25.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn temperature_at_interpolates_between_readings() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(10);
+        assert_eq!(history.temperature_at(start), None);
+
+        history.push(start, 20.0);
+        history.push(start + Duration::from_secs(10), 22.0);
+        history.push(start + Duration::from_secs(20), 21.0);
+
+        assert_eq!(
+            history.temperature_at(start + Duration::from_secs(5)),
+            Some(21.0)
+        );
+        assert_eq!(
+            history.temperature_at(start + Duration::from_secs(15)),
+            Some(21.5)
+        );
+        assert_eq!(
+            history.temperature_at(start + Duration::from_millis(2500)),
+            Some(20.5)
+        );
+    }
+
+    #[test]
+    fn temperature_at_boundaries() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(10);
+        history.push(start, 20.0);
+        history.push(start + Duration::from_secs(10), 22.0);
+
+        assert_eq!(history.temperature_at(start), Some(20.0));
+        assert_eq!(
+            history.temperature_at(start + Duration::from_secs(10)),
+            Some(22.0)
+        );
+        assert_eq!(
+            history.temperature_at(start + Duration::from_millis(10_001)),
+            None
+        );
+        assert_eq!(
+            history.temperature_at(start.checked_sub(Duration::from_millis(1)).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn rate_per_minute_uses_the_recent_window() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(100);
+        assert_eq!(history.rate_per_minute(Duration::from_secs(30)), None);
+
+        // flat for a minute, then rising 1°C every 10s
+        history.push(start, 20.0);
+        history.push(start + Duration::from_secs(60), 20.0);
+        for i in 1..=3 {
+            history.push(start + Duration::from_secs(60 + 10 * i), 20.0 + i as f32);
+        }
+
+        let rate = history.rate_per_minute(Duration::from_secs(30)).unwrap();
+        assert!((rate - 6.0).abs() < 1e-3, "{}", rate);
+        let overall = history.rate_per_minute(Duration::from_secs(600)).unwrap();
+        assert!(overall < rate);
+    }
+
+    #[test]
+    fn rate_is_taken_over_the_last_readings() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(20);
+        // slow at first, then one reading every 500 ms
+        history.push(start, 20.0);
+        for i in 0..10 {
+            history.push(start + Duration::from_millis(10_000 + 500 * i), 20.0);
+        }
+
+        assert_eq!(history.rate_hz(10), Some(2.0));
+        assert!(history.rate_hz(11).unwrap() < 2.0);
+        history.readings.truncate(1);
+        assert_eq!(history.rate_hz(10), None);
+    }
+
+    #[test]
+    fn readings_after_excludes_the_given_time() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(10);
+        for i in 0..4 {
+            history.push(secs(start, i), i as f32);
+        }
+
+        let after = |at| -> Vec<f32> { history.readings_after(at).map(|(_, c)| c).collect() };
+        assert_eq!(after(None), [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(after(Some(secs(start, 1))), [2.0, 3.0]);
+        assert!(after(Some(secs(start, 3))).is_empty());
+    }
+
+    #[test]
+    fn remove_before_keeps_the_extremes() {
+        let start = Instant::now();
+        let mut history = TemperatureHistory::with_capacity(10);
+        for i in 0..4 {
+            history.push(secs(start, i), i as f32);
+        }
+
+        history.remove_before(secs(start, 2));
+        let kept: Vec<f32> = history.iter().map(|&(_, c)| c).collect();
+        assert_eq!(kept, [2.0, 3.0]);
+        assert_eq!(history.min(), Some(0.0));
+        assert_eq!(history.max(), Some(3.0));
+    }
+
+    #[test]
+    fn resample_needs_two_readings() {
+        let mut history = TemperatureHistory::with_capacity(10);
+        assert!(history.resample(Duration::from_secs(1)).is_empty());
+        history.push(Instant::now(), 20.0);
+        assert!(history.resample(Duration::from_secs(1)).is_empty());
+    }
+
+    proptest! {
+        #[test]
+        fn mean_is_within_min_and_max(
+            values in prop::collection::vec(proptest::num::f32::NORMAL, 1..200),
+            capacity in 1usize..100,
+        ) {
+            let history = history_of(capacity, &values);
+            let mean = history.mean().unwrap();
+            prop_assert!(history.min().unwrap() <= mean);
+            prop_assert!(mean <= history.max().unwrap());
+        }
+
+        #[test]
+        fn std_dev_is_not_negative(
+            values in prop::collection::vec(proptest::num::f32::NORMAL, 1..200),
+        ) {
+            let history = history_of(values.len(), &values);
+            prop_assert!(history.std_dev().unwrap() >= 0.0);
+        }
+
+        #[test]
+        fn len_grows_up_to_capacity(
+            values in prop::collection::vec(proptest::num::f32::NORMAL, 0..200),
+            capacity in 1usize..100,
+        ) {
+            let mut history = TemperatureHistory::with_capacity(capacity);
+            let now = Instant::now();
+            for (i, &celsius) in values.iter().enumerate() {
+                history.push(now, celsius);
+                prop_assert_eq!(history.len(), (i + 1).min(capacity));
+            }
+        }
+
+        #[test]
+        fn full_history_stays_at_capacity(
+            values in prop::collection::vec(proptest::num::f32::NORMAL, 1..100),
+            next in proptest::num::f32::NORMAL,
+        ) {
+            let mut history = history_of(values.len(), &values);
+            history.readings.pop_front();
+            history.push(Instant::now(), next);
+            prop_assert_eq!(history.len(), history.max_len());
+        }
+    }
+}
@@ -0,0 +1,6 @@
+//! Decoding and history types shared by the binary and the benchmarks
+
+#![warn(clippy::must_use_candidate)]
+
+pub mod decode;
+pub mod history;
@@ -0,0 +1,49 @@
+use std::io::IsTerminal;
+use std::path::Path;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::{EnvFilter, LevelFilter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// Rotated log files kept next to `--log-file`
+const MAX_LOG_FILES: usize = 7;
+
+/// Log to stderr, filtered by `RUST_LOG` like `env_logger` did, and to a
+/// daily rotated `log_file` if given. `log` macros are forwarded as well.
+///
+/// The returned guard flushes the file when dropped, keep it alive until exit
+pub fn init(log_file: Option<&Path>) -> Result<Option<WorkerGuard>, Box<dyn std::error::Error>> {
+    let filter = EnvFilter::builder()
+        .with_default_directive(LevelFilter::ERROR.into())
+        .from_env_lossy();
+    let stderr = tracing_subscriber::fmt::layer()
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_writer(std::io::stderr);
+
+    let (file, guard) = match log_file {
+        Some(path) => {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let prefix = path.file_name().ok_or("--log-file needs a file name")?;
+            let appender = RollingFileAppender::builder()
+                .rotation(Rotation::DAILY)
+                .max_log_files(MAX_LOG_FILES)
+                .filename_prefix(prefix.to_string_lossy())
+                .build(dir)?;
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(writer);
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(stderr)
+        .with(file)
+        .try_init()?;
+    Ok(guard)
+}
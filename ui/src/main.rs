@@ -1,201 +1,838 @@
-use std::collections::VecDeque;
 use std::error::Error;
-use std::sync::mpsc::{Receiver, Sender};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use btleplug::api::bleuuid::uuid_from_u16;
-use btleplug::api::{Manager as _, Peripheral as _};
+use btleplug::api::{BDAddr, CentralEvent, Manager as _, Peripheral as _};
 use btleplug::{
     api::{Central, ScanFilter},
-    platform::{Adapter, Manager, Peripheral},
+    platform::Manager,
 };
 
+use clap::Parser;
 use eframe::egui;
-use egui::{Color32, Context};
-use egui_plot::{Legend, Line, LineStyle::Solid, Plot, PlotPoints};
+use egui::Context;
 
 use futures::stream::StreamExt;
-use log::{debug, info};
+use log::{error, info, warn};
+use regex::Regex;
+use tokio::sync::{oneshot, Notify};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+mod alarm;
+mod alert_sound;
+mod backend;
+mod ble;
+mod ble_log;
+mod chart;
+mod clock;
+mod config;
+#[cfg(feature = "env-config")]
+mod env_config;
+mod error;
+mod event_log;
+mod file_dialog;
+mod flush;
+mod headless;
+mod logging;
+mod metrics;
+mod overlay;
+mod session;
+mod session_file;
+mod signal;
+mod simulate;
+mod sink;
+mod stats;
+mod summary;
+mod ui;
+mod unit;
+
+use ble_temperature_ui::{decode, history};
+
+use ble::{
+    first_adapter, forward, parse_uuid, scan_devices, AddressType, BdAddrExt, ConnectionState,
+    DeviceFilter, Event, EventBus, ScanResult, TemperatureSendor, TemperatureSendorBuilder,
+};
+use ble_log::BleEventLog;
+use config::Config;
+use decode::DecoderKind;
+use error::BleTemperatureError;
+use metrics::Metrics;
+use overlay::Reference;
+use simulate::{parse_rate, SimulatedSensor};
+use sink::{CsvSink, NullSink, OutputFormatter, OutputSink, StdoutSink};
+use stats::TemperatureStats;
+use summary::SessionSummaryPrinter;
+use ui::UI;
+use unit::TemperatureUnit;
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Configuration file
+    #[arg(long, value_name = "PATH", default_value = "config.toml")]
+    config: PathBuf,
+
+    /// Write a documented example configuration to the `--config` path, adding
+    /// only missing keys if the file already exists, and exit
+    #[arg(long)]
+    generate_config: bool,
+
+    /// Print the JSON Schema of session files and exit
+    #[cfg(feature = "serde")]
+    #[arg(long)]
+    json_schema: bool,
+
+    /// Number of readings kept in memory [default: 300]
+    #[arg(long = "history")]
+    history_len: Option<usize>,
+
+    /// Upper bound for the in-memory history, in MB [default: 32]
+    #[arg(
+        long = "memory-limit",
+        value_name = "MB",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    memory_limit: Option<usize>,
+
+    /// Move in-memory readings to a compressed CSV file every N minutes
+    #[arg(long = "flush-interval", value_name = "MINUTES")]
+    flush_interval: Option<u64>,
+
+    /// Directory for flushed CSV files [default: .]
+    #[arg(long = "flush-dir", value_name = "PATH")]
+    flush_dir: Option<PathBuf>,
+
+    /// Label sensors whose name contains FILTER (or whose address is FILTER),
+    /// e.g. `--location Temperature01=Kitchen`
+    #[arg(long = "location", value_name = "FILTER=LABEL", value_parser = parse_location)]
+    locations: Vec<(String, String)>,
+
+    /// First wait before reconnecting, in seconds [default: 1]
+    #[arg(long = "reconnect-delay-initial", value_name = "SECONDS")]
+    reconnect_delay_initial: Option<u64>,
+
+    /// Longest wait between reconnection attempts, in seconds [default: 60]
+    #[arg(long = "reconnect-delay-max", value_name = "SECONDS")]
+    reconnect_delay_max: Option<u64>,
+
+    /// Give up after this many reconnection attempts in a row, exiting with
+    /// status 5 in headless mode. 0 keeps trying
+    #[arg(long = "max-reconnects", value_name = "N", default_value_t = 0)]
+    max_reconnects: u32,
+
+    /// Exit with status 6 when no sensor is found this many seconds after
+    /// startup. 0 keeps scanning
+    #[arg(long = "timeout-on-idle", value_name = "SECONDS", default_value_t = 0)]
+    timeout_on_idle: u64,
+
+    /// Raise an alarm above this temperature, in °C
+    #[arg(long = "alert-high", value_name = "CELSIUS")]
+    alert_high: Option<f32>,
+
+    /// Raise an alarm below this temperature, in °C
+    #[arg(long = "alert-low", value_name = "CELSIUS")]
+    alert_low: Option<f32>,
+
+    /// Show temperatures in this unit: celsius, c, fahrenheit or f
+    #[arg(long, value_name = "UNIT")]
+    unit: Option<TemperatureUnit>,
+
+    /// Show debugging information in the UI
+    #[arg(long)]
+    debug: bool,
+
+    /// Readings to wait for before showing the chart, for sensors that send
+    /// garbage while warming up
+    #[arg(long = "min-readings", value_name = "N", default_value_t = 1)]
+    min_readings: usize,
+
+    /// Log raw BLE notifications and connection events to a file
+    #[arg(long = "verbose-ble", value_name = "PATH")]
+    verbose_ble: Option<PathBuf>,
+
+    /// Append every reading to this CSV file as it arrives, creating it if absent
+    #[arg(long = "csv-append", value_name = "PATH")]
+    csv_append: Option<PathBuf>,
+
+    /// Also write log output to this file, rotated daily and keeping 7 files
+    #[arg(long = "log-file", value_name = "PATH")]
+    log_file: Option<PathBuf>,
+
+    /// Payload format of the sensor [default: detected from its advertisements]
+    #[arg(long, value_enum)]
+    decoder: Option<DecoderKind>,
+
+    /// Also take sensors advertising this GATT service, e.g. `0x1809` for
+    /// Health Thermometer, whatever their name
+    #[arg(long = "filter-service", value_name = "UUID", value_parser = parse_uuid)]
+    filter_service: Option<Uuid>,
+
+    /// Skip sensors with a weaker signal than this, in dBm
+    #[arg(long = "min-rssi", value_name = "DBM", allow_negative_numbers = true)]
+    min_rssi: Option<i16>,
+
+    /// Characteristic the readings are notified on [default: 0x2a1c]
+    #[arg(long, value_name = "UUID", value_parser = parse_uuid)]
+    characteristic: Option<Uuid>,
+
+    /// Also take the sensor with this address, e.g. `AA:BB:CC:DD:EE:FF`
+    #[arg(long = "filter-address", value_name = "ADDRESS", value_parser = BDAddr::from_colon_hex)]
+    filter_address: Option<BDAddr>,
+
+    /// Generate readings instead of connecting to a sensor, for trying out
+    /// the UI without hardware
+    #[arg(long)]
+    simulate: bool,
+
+    /// Seed of the `--simulate` noise, the same seed repeats the same readings
+    #[arg(
+        long = "sim-seed",
+        value_name = "SEED",
+        default_value_t = 0,
+        requires = "simulate"
+    )]
+    sim_seed: u64,
+
+    /// Fail this share of the decodes at random, e.g. `0.05` for 5%, to
+    /// exercise the error handling. Needs `--simulate` unless built with the
+    /// `testing` feature
+    #[arg(long = "inject-error", value_name = "RATE", value_parser = parse_rate)]
+    #[cfg_attr(not(feature = "testing"), arg(requires = "simulate"))]
+    inject_error: Option<f64>,
+
+    /// Address type of the sensor
+    #[arg(long, value_enum, default_value_t = AddressType::Auto)]
+    address_type: AddressType,
+
+    /// Session file drawn as a reference line on the chart
+    #[arg(long, value_name = "PATH")]
+    compare: Option<PathBuf>,
+
+    /// Print which BLE backend is active and exit
+    #[arg(long)]
+    ble_backend: bool,
+
+    /// List the available Bluetooth adapters and exit
+    #[arg(long)]
+    list_adapters: bool,
+
+    /// Check each step from the adapter to a decoded reading and print what
+    /// failed, with a suggested remedy
+    #[arg(long)]
+    diagnostics: bool,
+
+    /// Scan for the configured time and print the peripherals matching the
+    /// sensor filters, then exit
+    #[arg(long)]
+    scan: bool,
+
+    /// Print peripherals as they are discovered, until interrupted
+    #[arg(long)]
+    scan_continuous: bool,
+
+    /// Print the first reading and exit, without the UI
+    #[arg(long)]
+    once: bool,
+
+    /// Print `--once` output as JSON
+    #[arg(long, requires = "once")]
+    json: bool,
+
+    /// Collect readings, compare their mean to `--reference` and recommend a
+    /// `calibration_offset`
+    #[arg(long, requires = "reference")]
+    calibrate: bool,
+
+    /// Actual temperature while calibrating, from a trusted thermometer
+    #[arg(
+        long,
+        value_name = "CELSIUS",
+        requires = "calibrate",
+        allow_negative_numbers = true
+    )]
+    reference: Option<f32>,
+
+    /// Print readings instead of showing the UI
+    #[arg(long)]
+    headless: bool,
+
+    /// Stop recording after this many seconds and print a summary to stderr,
+    /// implies `--headless`
+    #[arg(long, value_name = "SECONDS")]
+    duration: Option<u64>,
+
+    /// Write headless readings to a file instead of stdout, as CSV or JSON
+    /// lines for `.csv` and `.jsonl` paths
+    #[arg(long, value_name = "PATH")]
+    output: Option<PathBuf>,
+
+    /// Format of headless readings [default: text, or by the `--output` extension]
+    #[arg(long, value_enum)]
+    format: Option<OutputFormatter>,
+
+    /// Print the newest headless reading once every interval instead of each
+    /// one as it arrives, `null` in JSON if none arrived
+    #[arg(long, value_name = "MS", value_parser = clap::value_parser!(u64).range(1..))]
+    headless_interval: Option<u64>,
+
+    /// Do not print headless readings, only the `--duration` summary
+    #[arg(long, conflicts_with = "output")]
+    quiet: bool,
+}
 
-fn main() -> Result<(), Box<dyn Error>> {
-    env_logger::init();
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Merge session JSON files into one, sorted by time
+    Merge {
+        #[arg(required = true, num_args = 2..)]
+        files: Vec<PathBuf>,
+
+        /// Merged session file
+        #[arg(short, long, value_name = "PATH")]
+        output: PathBuf,
+
+        /// Interpolate every sensor onto a common grid of this step
+        #[arg(long, value_name = "MS")]
+        resample: Option<u64>,
+    },
+}
 
-    let rt = tokio::runtime::Runtime::new()?;
+fn parse_location(s: &str) -> Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(filter, label)| (filter.to_owned(), label.to_owned()))
+        .ok_or_else(|| format!("expected FILTER=LABEL, got `{}`", s))
+}
 
-    let _enter = rt.enter();
+impl Args {
+    /// Override `config` with the options given on the command line
+    fn apply(&self, config: &mut Config) {
+        if let Some(history_len) = self.history_len {
+            config.history_len = history_len;
+        }
+        if let Some(memory_limit) = self.memory_limit {
+            config.memory_limit_mb = memory_limit;
+        }
+        if let Some(flush_interval) = self.flush_interval {
+            config.flush_interval_minutes = flush_interval;
+        }
+        if let Some(flush_dir) = &self.flush_dir {
+            config.flush_dir = flush_dir.clone();
+        }
+        config
+            .sensor_locations
+            .extend(self.locations.iter().cloned());
+        if let Some(secs) = self.reconnect_delay_initial {
+            config.reconnect.initial_delay_secs = secs;
+        }
+        if let Some(secs) = self.reconnect_delay_max {
+            config.reconnect.max_delay_secs = secs;
+        }
+        if self.alert_high.is_some() {
+            config.alert_high_celsius = self.alert_high;
+        }
+        if self.alert_low.is_some() {
+            config.alert_low_celsius = self.alert_low;
+        }
+        if let Some(unit) = self.unit {
+            config.display_unit = unit;
+        }
+    }
+}
 
-    let (tx, rx) = std::sync::mpsc::channel();
+fn handle_generate_config(path: &Path) -> Result<(), Box<dyn Error>> {
+    let content = if path.exists() {
+        Config::merge_example(&std::fs::read_to_string(path)?)?
+    } else {
+        Config::generate_example()
+    };
+    std::fs::write(path, content)?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
 
-    let sensor = TemperatureSendor::new(tx);
-    let ui = UI::new(rx);
+fn handle_merge(
+    files: &[PathBuf],
+    output: &Path,
+    resample: Option<u64>,
+) -> Result<(), Box<dyn Error>> {
+    let sessions = files
+        .iter()
+        .map(|path| {
+            session_file::load(path)
+                .map_err(|e| format!("failed to read {}: {}", path.display(), e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut records = session_file::merge(sessions);
+    if let Some(step) = resample {
+        records = session_file::resample(&records, step);
+    }
 
-    let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_inner_size([600.0, 400.0]),
-        default_theme: eframe::Theme::Light,
-        ..Default::default()
-    };
+    session_file::save(output, &records)?;
+    println!("wrote {} records to {}", records.len(), output.display());
+    Ok(())
+}
 
-    eframe::run_native(
-        "BLE Temperature",
-        options,
-        Box::new(|cc| {
-            let ctx = cc.egui_ctx.clone();
-            std::thread::spawn(move || {
-                rt.block_on(async {
-                    sensor.run(&ctx).await.unwrap();
-                });
-            });
+fn handle_ble_backend() {
+    println!("backend: {}", backend::active_backend());
 
-            Box::new(ui)
-        }),
-    )?;
+    let features = backend::enabled_features();
+    if features.is_empty() {
+        println!("features: none");
+    } else {
+        println!("features: {}", features.join(", "));
+    }
+}
+
+/// Print every adapter, returning whether any was found
+async fn handle_list_adapters() -> Result<bool, Box<dyn Error>> {
+    let manager = Manager::new().await?;
+    let adapters = manager.adapters().await?;
+
+    for (i, adapter) in adapters.iter().enumerate() {
+        match adapter.adapter_info().await {
+            Ok(info) => println!("{}: {}", i, info),
+            Err(e) => println!("{}: <unavailable: {}>", i, e),
+        }
+    }
+
+    Ok(!adapters.is_empty())
+}
+
+async fn handle_scan(duration: Duration, filter: &DeviceFilter) -> Result<(), Box<dyn Error>> {
+    let central = first_adapter().await?;
+    let found: Vec<ScanResult> = scan_devices(&central, duration, filter).await?;
+    central.stop_scan().await?;
+
+    for device in &found {
+        let services: Vec<_> = device.services.iter().map(Uuid::to_string).collect();
+        println!(
+            "{}\t{}\t{}\t{}",
+            device.address.to_colon_hex(),
+            device.name.as_deref().unwrap_or("<unnamed>"),
+            device
+                .rssi
+                .map_or("?".to_owned(), |rssi| format!("{} dBm", rssi)),
+            services.join(",")
+        );
+    }
+    if found.is_empty() {
+        println!("no matching peripherals found");
+    }
 
     Ok(())
 }
 
-struct TemperatureSendor {
-    tx: Sender<f32>,
+async fn handle_scan_continuous() -> Result<(), Box<dyn Error>> {
+    let central = first_adapter().await?;
+    let mut events = central.events().await?;
+    central.start_scan(ScanFilter::default()).await?;
+
+    while let Some(event) = events.next().await {
+        let CentralEvent::DeviceDiscovered(id) = event else {
+            continue;
+        };
+        let properties = central.peripheral(&id).await?.properties().await?;
+        let name = properties.as_ref().and_then(|p| p.local_name.as_deref());
+        let rssi = properties.as_ref().and_then(|p| p.rssi);
+        // the id is a D-Bus path or a UUID depending on the platform
+        let address = properties
+            .as_ref()
+            .map_or_else(|| id.to_string(), |p| p.address.to_colon_hex());
+
+        println!(
+            "{}\t{}\t{}",
+            address,
+            name.unwrap_or("<unnamed>"),
+            rssi.map_or("?".to_owned(), |rssi| format!("{} dBm", rssi))
+        );
+    }
+
+    Ok(())
 }
 
-impl TemperatureSendor {
-    fn new(tx: Sender<f32>) -> Self {
-        Self { tx }
+/// Print the outcome of each check, returning whether all passed
+async fn handle_diagnostics(mut sensor: TemperatureSendor) -> bool {
+    let checks = sensor.diagnose().await;
+    for check in &checks {
+        println!("{}", check);
     }
+    checks.iter().all(|check| check.passed())
+}
 
-    async fn run(&self, egui_ctx: &Context) -> Result<(), Box<dyn Error>> {
-        let manager = Manager::new().await?;
+/// Single session without reconnecting, printing its first reading
+async fn handle_once(
+    mut sensor: TemperatureSendor,
+    json: bool,
+    cancel: &CancellationToken,
+) -> Result<(), Box<dyn Error>> {
+    let (tx, rx) = oneshot::channel();
+    sensor.once = Some(tx);
+
+    if sensor.is_simulated() {
+        sensor.run(&Context::default(), cancel).await?;
+    } else {
+        let central = first_adapter().await?;
+        sensor
+            .session(&central, &Context::default(), cancel)
+            .await?;
+    }
 
-        // get the first bluetooth adapter
-        let adapters = manager.adapters().await?;
-        let central = adapters
-            .into_iter()
-            .nth(0)
-            .ok_or(btleplug::Error::DeviceNotFound)?;
+    let measurement = rx.await.map_err(|_| "sensor went away before a reading")?;
+    if json {
+        println!("{{\"celsius\":{:.1}}}", measurement.celsius);
+    } else {
+        println!("{}", measurement);
+    }
+    Ok(())
+}
 
-        // start scanning for devices
-        central.start_scan(ScanFilter::default()).await?;
-        tokio::time::sleep(Duration::from_secs(2)).await;
+/// Events a subscriber of the sensor task may fall behind by before missing some
+const EVENT_BUS_CAPACITY: usize = 1024;
 
-        // find the sensor
-        let sensor = self.find_sensor(&central).await?;
+/// Longest the sensor task may take to shut down after the UI closes
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
 
-        info!("connecting to sensor: {}", sensor.address());
-        sensor.connect().await?;
+/// Readings averaged by `--calibrate`
+const CALIBRATION_READINGS: u64 = 30;
 
-        info!("discovering services");
-        sensor.discover_services().await?;
+/// Gives up calibrating when the readings take longer than this
+const CALIBRATION_TIMEOUT: Duration = Duration::from_secs(120);
 
-        info!("findind temperature characteristic");
-        let chars = sensor.characteristics();
-        let notify_char = chars
-            .iter()
-            .find(|c| c.uuid == uuid_from_u16(0x2a1c))
-            .ok_or(btleplug::Error::NoSuchCharacteristic)?;
+/// Up to `CALIBRATION_READINGS` readings of the sensor
+async fn collect_calibration(
+    mut sensor: TemperatureSendor,
+    rx: Receiver<Event>,
+    cancel: &CancellationToken,
+) -> Result<TemperatureStats, Box<dyn Error>> {
+    let collector = std::thread::spawn(move || headless::collect(rx, CALIBRATION_READINGS));
 
-        info!("subscribing to characteristic");
-        sensor.subscribe(notify_char).await?;
+    // ends once the collector has enough and hangs up
+    let ctx = Context::default();
+    let result = tokio::time::timeout(CALIBRATION_TIMEOUT, sensor.run(&ctx, cancel))
+        .await
+        .unwrap_or(Ok(()));
 
-        let mut stream = sensor.notifications().await?;
+    drop(sensor);
+    let stats = collector.join().expect("collector thread panicked");
+    result.map(|()| stats)
+}
 
-        while let Some(data) = stream.next().await {
-            if let Some(temp) = self.decode(&data.value) {
-                self.tx.send(temp)?;
-                egui_ctx.request_repaint()
-            }
-        }
+/// Recommend the offset that makes the mean of `stats` read `reference`, and
+/// store it in the config file at `path` if confirmed
+fn handle_calibrate(
+    stats: &TemperatureStats,
+    reference: f32,
+    mut config: Config,
+    path: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let mean = stats.mean().ok_or("no readings to calibrate with")?;
+    if stats.count < CALIBRATION_READINGS {
+        eprintln!(
+            "only {} of {} readings arrived in time",
+            stats.count, CALIBRATION_READINGS
+        );
+    }
 
-        Ok(())
-    }
-
-    async fn find_sensor(&self, central: &Adapter) -> Result<Peripheral, btleplug::Error> {
-        for p in central.peripherals().await? {
-            if p.properties()
-                .await
-                .unwrap()
-                .unwrap()
-                .local_name
-                .iter()
-                .any(|name| {
-                    info!("discover sensor: {}", name);
-                    name.contains("Temperature")
-                })
-            {
-                return Ok(p);
-            }
-        }
+    // readings already include the configured offset
+    let offset = config.calibration_offset + f64::from(reference - mean);
+    let offset = (offset * 10.0).round() / 10.0;
+    println!(
+        "Mean of {} readings: {:.2}°C, reference: {:.2}°C",
+        stats.count, mean, reference
+    );
+    println!("Recommended calibration_offset: {:+.1}°C", offset);
+
+    print!("Write it to {}? [y/N] ", path.display());
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    if answer.trim().eq_ignore_ascii_case("y") {
+        config.calibration_offset = offset;
+        config.save_keys(path, &["calibration_offset"])?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
 
-        Err(btleplug::Error::DeviceNotFound)
+/// Record readings without the UI, for `duration` or until the sensor task fails
+async fn handle_headless(
+    mut sensor: TemperatureSendor,
+    rx: Receiver<Event>,
+    duration: Option<Duration>,
+    interval: Option<Duration>,
+    sink: Box<dyn OutputSink + Send>,
+    cancel: &CancellationToken,
+) -> Result<(), Box<dyn Error>> {
+    let handle = tokio::runtime::Handle::current();
+    let recorder = std::thread::spawn(move || match interval {
+        Some(interval) => headless::record_every(rx, sink, interval, handle),
+        None => headless::record(rx, sink),
+    });
+
+    let ctx = Context::default();
+    let result = match duration {
+        Some(duration) => tokio::time::timeout(duration, sensor.run(&ctx, cancel))
+            .await
+            .unwrap_or(Ok(())),
+        None => sensor.run(&ctx, cancel).await,
+    };
+
+    // closes the channel so the recorder finishes
+    drop(sensor);
+    let stats = recorder.join().expect("recorder thread panicked")?;
+    if duration.is_some() {
+        eprintln!("{}", headless::summary_json(&stats));
     }
 
-    fn decode(&self, buf: &[u8]) -> Option<f32> {
-        if buf.len() != 5 {
-            return None;
-        }
-        let is_fahrenheit = buf[0] == 1;
+    result
+}
 
-        let value = u32::from_le_bytes(buf[1..].try_into().unwrap()) & 0x00ffffff;
-        debug!("temp: {}", value);
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let _log_guard = logging::init(args.log_file.as_deref())?;
+    if let Some(path) = &args.log_file {
+        info!("logging to {}", path.display());
+    }
+    for feature in backend::mismatched_features() {
+        warn!(
+            "the `{}` feature is for another platform, this build uses {}",
+            feature,
+            backend::active_backend()
+        );
+    }
 
-        let mut value = value as f32 / 1000.0;
-        if is_fahrenheit {
-            value = (value - 32.0) / 1.8;
-        }
+    if let Some(Command::Merge {
+        files,
+        output,
+        resample,
+    }) = &args.command
+    {
+        return handle_merge(files, output, *resample);
+    }
 
-        Some(value)
+    if args.ble_backend {
+        handle_ble_backend();
+        return Ok(());
     }
-}
 
-struct UI {
-    rx: Receiver<f32>,
-    measures: VecDeque<f32>,
-}
+    if args.list_adapters {
+        let found = tokio::runtime::Runtime::new()?.block_on(handle_list_adapters())?;
+        std::process::exit(if found { 0 } else { 1 });
+    }
 
-impl UI {
-    fn new(rx: Receiver<f32>) -> Self {
-        Self {
-            measures: VecDeque::with_capacity(10),
-            rx,
-        }
+    if args.scan_continuous {
+        return tokio::runtime::Runtime::new()?.block_on(handle_scan_continuous());
     }
-}
 
-impl eframe::App for UI {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // receive temperature
-        if let Ok(temp) = self.rx.try_recv() {
-            if self.measures.len() >= self.measures.capacity() {
-                self.measures.pop_front();
-            }
-            self.measures.push_back(temp);
+    if args.generate_config {
+        return handle_generate_config(&args.config);
+    }
+
+    #[cfg(feature = "serde")]
+    if args.json_schema {
+        println!("{}", session_file::json_schema());
+        return Ok(());
+    }
+
+    let mut config = Config::load(&args.config)?;
+    #[cfg(feature = "env-config")]
+    env_config::apply(&mut config)?;
+    args.apply(&mut config);
+
+    let rt = tokio::runtime::Runtime::new()?;
+
+    let _enter = rt.enter();
+
+    let (tx, events) = std::sync::mpsc::channel::<Event>();
+    // cancelled once the UI closes, for the sensor task to disconnect
+    let cancel = CancellationToken::new();
+
+    let notify_instant = Arc::new(Mutex::new(None));
+    let metrics = Arc::new(Metrics::default());
+
+    let mut filters = vec![DeviceFilter::ByName(Regex::new(
+        &config.sensor_name_filter,
+    )?)];
+    filters.extend(args.filter_service.map(DeviceFilter::ByServiceUuid));
+    filters.extend(args.filter_address.map(DeviceFilter::ByAddress));
+    let filter = match filters.len() {
+        1 => filters.remove(0),
+        _ => DeviceFilter::Any(filters),
+    };
+
+    if args.scan {
+        let duration = Duration::from_secs(config.scan_duration_secs);
+        return rt.block_on(handle_scan(duration, &filter));
+    }
+
+    if args.address_type == AddressType::Random {
+        info!("random addresses may rotate, the sensor is rediscovered by name after each change");
+    }
+    let mut builder = TemperatureSendorBuilder::new();
+    builder
+        .device_filter(filter)
+        .decoder(args.decoder)
+        .address_type(args.address_type)
+        .rssi_minimum(args.min_rssi)
+        .scan_duration(Duration::from_secs(config.scan_duration_secs))
+        .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+        .max_retries(config.max_scan_retries)
+        .calibration_offset(config.calibration_offset as f32)
+        .backoff(
+            Duration::from_secs(config.reconnect.initial_delay_secs),
+            Duration::from_secs(config.reconnect.max_delay_secs),
+        )
+        .max_reconnects((args.max_reconnects > 0).then_some(args.max_reconnects))
+        .idle_timeout((args.timeout_on_idle > 0).then(|| Duration::from_secs(args.timeout_on_idle)))
+        .notify_instant(notify_instant.clone())
+        .metrics(metrics.clone());
+    if let Some(uuid) = args.characteristic {
+        builder.notify_char_uuid(uuid);
+    }
+    if args.simulate {
+        builder.simulate(SimulatedSensor::new(args.sim_seed));
+    }
+    if let Some(rate) = args.inject_error {
+        builder.inject_errors(rate);
+    }
+    if let Some(path) = &args.verbose_ble {
+        builder.ble_log(BleEventLog::create(path)?);
+    }
+    if let Some(path) = &args.csv_append {
+        let log = CsvSink::append(path)
+            .map_err(|e| format!("failed to open {}: {}", path.display(), e))?;
+        builder.csv_log(log);
+    }
+    // the UI channel is one subscriber, the sensor task owns the only sender
+    // so the channel closes with it
+    let bus = EventBus::new(EVENT_BUS_CAPACITY);
+    rt.spawn(forward(bus.subscriber(), tx, metrics.clone()));
+    let sensor = builder.build(bus);
+
+    if args.once {
+        return rt.block_on(handle_once(sensor, args.json, &cancel));
+    }
+    if args.diagnostics {
+        let passed = rt.block_on(handle_diagnostics(sensor));
+        std::process::exit(if passed { 0 } else { 1 });
+    }
+    if let (true, Some(reference)) = (args.calibrate, args.reference) {
+        let stats = rt.block_on(collect_calibration(sensor, events, &cancel))?;
+        return handle_calibrate(&stats, reference, config, &args.config);
+    }
+
+    let summary = SessionSummaryPrinter::new(metrics.clone());
+    rt.spawn(summary.exit_on_interrupt());
+
+    if args.headless || args.duration.is_some() {
+        let duration = args.duration.map(Duration::from_secs);
+        let interval = args.headless_interval.map(Duration::from_millis);
+        let sink: Box<dyn OutputSink + Send> = match &args.output {
+            Some(path) => sink::for_path(path, args.format)?,
+            None if args.quiet => Box::new(NullSink),
+            None => Box::new(StdoutSink::new(
+                args.format.unwrap_or(OutputFormatter::Text),
+            )),
+        };
+        let result = rt.block_on(handle_headless(
+            sensor, events, duration, interval, sink, &cancel,
+        ));
+        if let Some(e) = result
+            .as_ref()
+            .err()
+            .and_then(|e| e.downcast_ref::<BleTemperatureError>())
+        {
+            let code = match e {
+                BleTemperatureError::TooManyReconnects(_) => 5,
+                BleTemperatureError::IdleTimeout(_) => 6,
+                _ => return result,
+            };
+            error!("{}", e);
+            // the summary is printed on drop, which exit would skip
+            drop(summary);
+            std::process::exit(code);
         }
+        return result;
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("BLE Tempereture");
-
-            let plot = Plot::new("tempereture")
-                .legend(Legend::default())
-                .include_y(30.0)
-                .include_y(15.0)
-                .show_axes([false, true])
-                .show_x(false)
-                .show_grid(true);
-
-            plot.show(ui, |plot_ui| {
-                let points: PlotPoints = self
-                    .measures
-                    .iter()
-                    .enumerate()
-                    .map(|(i, x)| [i as f64, *x as f64])
-                    .collect();
-
-                let line = Line::new(points)
-                    .color(Color32::from_rgb(100, 200, 100))
-                    .style(Solid)
-                    .highlight(true)
-                    .name("Tempereture");
-
-                plot_ui.line(line);
-            })
-        });
+    let options = eframe::NativeOptions::from(&config);
+    let retry = Arc::new(Notify::new());
+    // dropped when the sensor thread ends, after sending the exit code if it
+    // has to end the process
+    let (running, stopped) = std::sync::mpsc::channel::<i32>();
+    let task_cancel = cancel.clone();
+    let mut ui = UI::new(events, config, args.config.clone(), notify_instant, metrics);
+    ui.debug = args.debug;
+    ui.min_readings = args.min_readings;
+    ui.warmup_remaining = args.min_readings;
+    if let Some(path) = &args.compare {
+        let reference = Reference::load(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        ui.reference = Some(reference);
     }
+    ui.retry = retry.clone();
+
+    eframe::run_native(
+        "BLE Temperature Monitor",
+        options,
+        Box::new(|cc| {
+            let ctx = cc.egui_ctx.clone();
+            std::thread::spawn(move || {
+                let running = running;
+                let mut sensor = sensor;
+                rt.block_on(async {
+                    loop {
+                        match sensor.run(&ctx, &task_cancel).await {
+                            // only returns once the UI is gone
+                            Ok(()) => break,
+                            Err(e) => {
+                                if let Some(e @ BleTemperatureError::IdleTimeout(_)) =
+                                    e.downcast_ref::<BleTemperatureError>()
+                                {
+                                    error!("{}", e);
+                                    // main exits once the UI is closed
+                                    let _ = running.send(6);
+                                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                                    break;
+                                }
+                                error!("sensor task failed: {}", e);
+                                let event = match e.downcast_ref::<BleTemperatureError>() {
+                                    Some(BleTemperatureError::NoAdapter) => {
+                                        Event::State(ConnectionState::NoAdapter)
+                                    }
+                                    _ => Event::Error(e.to_string()),
+                                };
+                                // the UI may already be gone, nothing left to tell then
+                                let _ = sensor.send(event);
+                                ctx.request_repaint();
+                            }
+                        }
+                        tokio::select! {
+                            _ = retry.notified() => {}
+                            _ = task_cancel.cancelled() => break,
+                        }
+                    }
+                });
+            });
+
+            Box::new(ui)
+        }),
+    )?;
+
+    // let the sensor task disconnect, unless it is stuck scanning or connecting
+    cancel.cancel();
+    if let Ok(code) = stopped.recv_timeout(SHUTDOWN_GRACE) {
+        // the summary is printed on drop, which exit would skip
+        drop(summary);
+        std::process::exit(code);
+    }
+    Ok(())
 }
@@ -0,0 +1,25 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize};
+use std::sync::Mutex;
+
+use crate::stats::TemperatureStats;
+
+/// Counters shared between the sensor task and the UI
+#[derive(Default)]
+pub struct Metrics {
+    /// Currently connected sensors
+    pub connections: AtomicUsize,
+    /// Notifications received from all sensors
+    pub notifications: AtomicU64,
+    /// Payload bytes of all notifications
+    pub bytes_received: AtomicU64,
+    /// Notifications decoded into a reading
+    pub decoded: AtomicU64,
+    /// Notifications that could not be decoded
+    pub decode_failures: AtomicU64,
+    /// Events sent to the UI but not yet received
+    pub queue_depth: AtomicUsize,
+    /// Every decoded reading since startup
+    pub readings: Mutex<TemperatureStats>,
+    /// Threshold breaches since startup
+    pub alarms: AtomicU64,
+}
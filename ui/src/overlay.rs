@@ -0,0 +1,110 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use egui_plot::LineStyle;
+use flate2::read::GzDecoder;
+
+use crate::clock;
+use crate::session_file;
+
+/// At most this many overlays are shown at once
+pub const MAX_OVERLAYS: usize = 4;
+
+/// Line style of the `n`-th overlay
+pub fn line_style(n: usize) -> LineStyle {
+    match n % MAX_OVERLAYS {
+        0 => LineStyle::Dashed { length: 5.0 },
+        1 => LineStyle::Dotted { spacing: 5.0 },
+        2 => LineStyle::Dashed { length: 12.0 },
+        _ => LineStyle::Dotted { spacing: 10.0 },
+    }
+}
+
+/// Previously recorded readings drawn alongside the live data
+pub struct Overlay {
+    pub name: String,
+    /// `[seconds since the first reading, celsius]`
+    pub points: Vec<[f64; 2]>,
+}
+
+impl Overlay {
+    /// Read a `timestamp,celsius` file, optionally gzip-compressed. Timestamps
+    /// are Unix milliseconds or local times as in CSV exports
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+            Box::new(GzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+
+        let mut rows = Vec::new();
+        for line in BufReader::new(reader).lines().skip(1) {
+            let line = line?;
+            let mut fields = line.split(',');
+            let ts = fields.next().and_then(|ts| clock::parse_ms(ts.trim()));
+            let celsius = fields.next().and_then(|c| c.trim().parse::<f64>().ok());
+            if let (Some(ts), Some(celsius)) = (ts, celsius) {
+                rows.push((ts, celsius));
+            }
+        }
+
+        let first = rows.first().map_or(0, |(ts, _)| *ts);
+        let points = rows
+            .into_iter()
+            .map(|(ts, celsius)| [ts.saturating_sub(first) as f64 / 1000.0, celsius])
+            .collect();
+
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+
+        Ok(Self { name, points })
+    }
+}
+
+/// A session file shown for comparison, starting at `offset_secs` on the live chart
+pub struct Reference {
+    /// `[seconds since the first record, celsius]`
+    pub points: Vec<[f64; 2]>,
+    /// Wall-clock time of the first record
+    pub start_ms: u64,
+    pub offset_secs: f64,
+}
+
+impl Reference {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let records = session_file::load(path)?;
+        let start_ms = records.iter().map(|r| r.ts_ms).min().unwrap_or(0);
+        let points = records
+            .iter()
+            .map(|r| [(r.ts_ms - start_ms) as f64 / 1000.0, r.celsius as f64])
+            .collect();
+
+        Ok(Self {
+            points,
+            start_ms,
+            offset_secs: 0.0,
+        })
+    }
+
+    /// Legend entry such as `Reference: -2:34:12 offset`, the time between the
+    /// recording and the part of the live chart it is drawn over
+    pub fn name(&self, chart_start_ms: u64) -> String {
+        let drawn_at_ms = chart_start_ms as f64 + self.offset_secs * 1000.0;
+        let offset = ((self.start_ms as f64 - drawn_at_ms) / 1000.0).round() as i64;
+
+        let sign = if offset < 0 { "-" } else { "" };
+        let offset = offset.unsigned_abs();
+        format!(
+            "Reference: {}{}:{:02}:{:02} offset",
+            sign,
+            offset / 3600,
+            offset / 60 % 60,
+            offset % 60
+        )
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::{Instant, SystemTime};
+
+use crate::alarm::{AlarmKind, AlarmLog};
+use crate::ble::device_info::DeviceInfo;
+use crate::event_log::{EventLog, LogEvent};
+use crate::history::TemperatureHistory;
+use crate::stats::TemperatureStats;
+
+/// Everything recorded between connecting to a sensor and losing it
+pub struct TemperatureSession {
+    pub history: TemperatureHistory,
+    pub stats: TemperatureStats,
+    pub alarm_log: AlarmLog,
+    pub event_log: EventLog,
+    pub start_time: SystemTime,
+    pub sensor_info: Option<DeviceInfo>,
+    /// Exponential moving average of the readings
+    pub smoothed: Option<f32>,
+}
+
+impl TemperatureSession {
+    pub fn new(history_len: usize) -> Self {
+        Self {
+            history: TemperatureHistory::with_capacity(history_len),
+            stats: TemperatureStats::default(),
+            alarm_log: AlarmLog::default(),
+            event_log: EventLog::default(),
+            start_time: SystemTime::now(),
+            sensor_info: None,
+            smoothed: None,
+        }
+    }
+
+    /// Store a reading, returning the alarm it raised if any. `alpha` is the
+    /// weight of the reading in `smoothed`
+    pub fn record(
+        &mut self,
+        at: Instant,
+        celsius: f32,
+        alpha: f32,
+        high: Option<f32>,
+        low: Option<f32>,
+        hysteresis: f32,
+    ) -> Option<AlarmKind> {
+        self.history.push(at, celsius);
+        self.stats += celsius;
+        self.smoothed = Some(match self.smoothed {
+            Some(smoothed) => smoothed + alpha.clamp(0.0, 1.0) * (celsius - smoothed),
+            None => celsius,
+        });
+        self.event_log.push(LogEvent::ReadingReceived { celsius });
+
+        let alarm = self.alarm_log.check(celsius, high, low, hysteresis);
+        if let Some(kind) = alarm {
+            self.event_log.push(LogEvent::AlertFired { celsius, kind });
+        }
+        alarm
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::time::{Instant, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::flush;
+use crate::history::TemperatureHistory;
+
+/// One reading of a session file, which is a JSON array of these. Always
+/// serializable and keeping its snake_case keys, the file format depends on it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "serde", derive(schemars::JsonSchema))]
+pub struct SensorRecord {
+    /// Unix time in milliseconds
+    pub ts_ms: u64,
+    /// Label of the sensor, empty when it had none
+    #[serde(default)]
+    pub sensor: String,
+    pub celsius: f32,
+}
+
+/// JSON Schema (draft 7) of a session file
+#[cfg(feature = "serde")]
+pub fn json_schema() -> String {
+    let schema = schemars::schema_for!(Vec<SensorRecord>);
+    serde_json::to_string_pretty(&schema).expect("schemas serialize")
+}
+
+pub fn load(path: &Path) -> Result<Vec<SensorRecord>, Box<dyn Error>> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+pub fn save(path: &Path, records: &[SensorRecord]) -> Result<(), Box<dyn Error>> {
+    serde_json::to_writer(BufWriter::new(File::create(path)?), records)?;
+    Ok(())
+}
+
+/// Records of the in-memory readings of `history` labelled as `sensor`
+pub fn from_readings(history: &TemperatureHistory, sensor: &str) -> Vec<SensorRecord> {
+    let now = (Instant::now(), SystemTime::now());
+    history
+        .iter()
+        .map(|(t, celsius)| SensorRecord {
+            ts_ms: flush::unix_ms(now, *t) as u64,
+            sensor: sensor.to_owned(),
+            celsius: *celsius,
+        })
+        .collect()
+}
+
+/// All records sorted by time, dropping exact duplicates of a sensor's timestamp
+pub fn merge(files: Vec<Vec<SensorRecord>>) -> Vec<SensorRecord> {
+    let mut records: Vec<SensorRecord> = files.into_iter().flatten().collect();
+    records.sort_by(|a, b| a.ts_ms.cmp(&b.ts_ms).then_with(|| a.sensor.cmp(&b.sensor)));
+    records.dedup_by(|a, b| a.ts_ms == b.ts_ms && a.sensor == b.sensor);
+    records
+}
+
+/// Linearly interpolate every sensor onto a common `step_ms` grid, each
+/// only within the time span it has readings for
+pub fn resample(records: &[SensorRecord], step_ms: u64) -> Vec<SensorRecord> {
+    let (Some(first), Some(step)) = (records.first(), (step_ms > 0).then_some(step_ms)) else {
+        return Vec::new();
+    };
+    let origin = first.ts_ms;
+
+    let mut by_sensor: BTreeMap<&str, Vec<&SensorRecord>> = BTreeMap::new();
+    for record in records {
+        by_sensor.entry(&record.sensor).or_default().push(record);
+    }
+
+    let mut resampled = Vec::new();
+    for (sensor, series) in by_sensor {
+        let (start, end) = (series[0].ts_ms, series[series.len() - 1].ts_ms);
+        // first grid point at or after the series start
+        let mut ts = origin + (start - origin).div_ceil(step) * step;
+        let mut i = 0;
+
+        while ts <= end {
+            while series[i + 1..].first().is_some_and(|next| next.ts_ms < ts) {
+                i += 1;
+            }
+            let a = series[i];
+            let celsius = match series.get(i + 1) {
+                Some(b) if b.ts_ms > a.ts_ms && ts > a.ts_ms => {
+                    let t = (ts - a.ts_ms) as f32 / (b.ts_ms - a.ts_ms) as f32;
+                    a.celsius + (b.celsius - a.celsius) * t
+                }
+                _ => a.celsius,
+            };
+
+            resampled.push(SensorRecord {
+                ts_ms: ts,
+                sensor: sensor.to_owned(),
+                celsius,
+            });
+            ts += step;
+        }
+    }
+
+    resampled.sort_by(|a, b| a.ts_ms.cmp(&b.ts_ms).then_with(|| a.sensor.cmp(&b.sensor)));
+    resampled
+}
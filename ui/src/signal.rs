@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+/// Number of RSSI readings the quality is computed over
+const WINDOW: usize = 10;
+
+/// Recent RSSI readings of a sensor
+#[derive(Default)]
+pub struct RssiHistory {
+    readings: VecDeque<i16>,
+}
+
+impl RssiHistory {
+    pub fn push(&mut self, rssi: i16) {
+        if self.readings.len() >= WINDOW {
+            self.readings.pop_front();
+        }
+        self.readings.push_back(rssi);
+    }
+
+    pub fn current(&self) -> Option<i16> {
+        self.readings.back().copied()
+    }
+
+    pub fn mean(&self) -> Option<f32> {
+        if self.readings.is_empty() {
+            return None;
+        }
+        let sum: f32 = self.readings.iter().map(|&r| r as f32).sum();
+        Some(sum / self.readings.len() as f32)
+    }
+
+    pub fn std_dev(&self) -> Option<f32> {
+        let mean = self.mean()?;
+        let variance: f32 = self
+            .readings
+            .iter()
+            .map(|&r| (r as f32 - mean).powi(2))
+            .sum::<f32>()
+            / self.readings.len() as f32;
+        Some(variance.sqrt())
+    }
+
+    /// Signal bars from 1 (very poor) to 5 (excellent)
+    pub fn level(&self) -> Option<usize> {
+        let level = match self.mean()? {
+            mean if mean > -60.0 => 5,
+            mean if mean > -70.0 => 4,
+            mean if mean > -80.0 => 3,
+            mean if mean > -90.0 => 2,
+            _ => 1,
+        };
+        Some(level)
+    }
+
+    pub fn quality(&self) -> Option<&'static str> {
+        let level = self.level()?;
+        Some(["very poor", "poor", "fair", "good", "excellent"][level - 1])
+    }
+
+    /// How steady the link is, a high deviation means fading
+    pub fn stability(&self) -> Option<&'static str> {
+        let stability = match self.std_dev()? {
+            dev if dev < 3.0 => "stable",
+            dev if dev < 6.0 => "variable",
+            _ => "fading",
+        };
+        Some(stability)
+    }
+}
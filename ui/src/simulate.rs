@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::decode::TemperatureDecoder;
+
+/// Largest deviation of the noise added to each reading, in °C
+const NOISE_CELSIUS: f32 = 0.1;
+
+/// Stands in for a sensor with `23.0 + 2.0 * sin(t * 0.1)` plus noise, `t` in
+/// seconds since the start. The same seed gives the same readings
+pub struct SimulatedSensor {
+    rng: SmallRng,
+    /// Time between notifications
+    pub interval: Duration,
+}
+
+impl SimulatedSensor {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: SmallRng::seed_from_u64(seed),
+            interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Temperature `elapsed` into the simulation
+    pub fn reading(&mut self, elapsed: Duration) -> f32 {
+        let t = elapsed.as_secs_f32();
+        23.0 + 2.0 * (t * 0.1).sin() + self.rng.gen_range(-NOISE_CELSIUS..=NOISE_CELSIUS)
+    }
+
+    /// The reading `elapsed` into the simulation, as a Health Thermometer
+    /// notification in °C
+    pub fn notification(&mut self, elapsed: Duration) -> Vec<u8> {
+        let milli = (self.reading(elapsed) * 1000.0).round() as u32;
+        let [b0, b1, b2, _] = milli.to_le_bytes();
+        vec![0x00, b0, b1, b2, 0x00]
+    }
+}
+
+/// Fails a `rate` share of the decodes of `D` at random, for exercising the
+/// decode failure handling, see `--inject-error`
+pub struct InjectErrors<D> {
+    decoder: D,
+    rate: f64,
+}
+
+impl<D> InjectErrors<D> {
+    /// `rate` is clamped to `0.0..=1.0`
+    pub fn new(decoder: D, rate: f64) -> Self {
+        Self {
+            decoder,
+            rate: rate.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl<D: TemperatureDecoder> TemperatureDecoder for InjectErrors<D> {
+    fn decode(&self, buf: &[u8]) -> Option<f32> {
+        if rand::thread_rng().gen_bool(self.rate) {
+            return None;
+        }
+        self.decoder.decode(buf)
+    }
+}
+
+/// A probability for `--inject-error`
+pub fn parse_rate(s: &str) -> Result<f64, String> {
+    let rate: f64 = s.parse().map_err(|e| format!("{}", e))?;
+    if (0.0..=1.0).contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(format!("expected a rate between 0 and 1, got {}", rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::{HealthThermometerDecoder, TemperatureMeasurement};
+
+    #[test]
+    fn same_seed_same_readings() {
+        let (mut a, mut b) = (SimulatedSensor::new(7), SimulatedSensor::new(7));
+        for i in 0..10 {
+            let at = Duration::from_secs(i);
+            assert_eq!(a.reading(at), b.reading(at));
+        }
+        assert_ne!(
+            SimulatedSensor::new(8).reading(Duration::ZERO),
+            SimulatedSensor::new(7).reading(Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn notifications_decode_to_the_model() {
+        let mut sensor = SimulatedSensor::new(1);
+        for i in 0..60 {
+            let at = Duration::from_secs(i);
+            let model = 23.0 + 2.0 * (i as f32 * 0.1).sin();
+            let celsius = TemperatureMeasurement::try_from(&sensor.notification(at)[..])
+                .unwrap()
+                .celsius;
+            assert!(
+                (celsius - model).abs() <= NOISE_CELSIUS + 0.001,
+                "{}",
+                celsius
+            );
+        }
+    }
+
+    #[test]
+    fn injected_errors_follow_the_rate() {
+        let payload = SimulatedSensor::new(0).notification(Duration::ZERO);
+        assert!(InjectErrors::new(HealthThermometerDecoder, 0.0)
+            .decode(&payload)
+            .is_some());
+        assert!(InjectErrors::new(HealthThermometerDecoder, 1.0)
+            .decode(&payload)
+            .is_none());
+
+        assert_eq!(parse_rate("0.05"), Ok(0.05));
+        assert!(parse_rate("1.5").is_err());
+        assert!(parse_rate("-0.1").is_err());
+    }
+}
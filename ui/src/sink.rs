@@ -0,0 +1,214 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::clock;
+use crate::decode::TemperatureMeasurement;
+
+/// Where headless mode writes its readings
+pub trait OutputSink {
+    fn write_reading(&mut self, reading: &TemperatureMeasurement) -> io::Result<()>;
+
+    /// Called for an output tick without a new reading. Writes nothing unless
+    /// the format has a way to say so
+    fn write_missing(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Line formats of headless output
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormatter {
+    /// `2024-01-15 14:32:05 | 23.4°C`
+    Text,
+    /// One `{"timestamp": .., "celsius": ..}` object per line
+    Json,
+    /// `timestamp,celsius` rows, in local time like `2024-01-15T14:32:05.123`
+    /// with the `chrono` feature and in Unix milliseconds without
+    Csv,
+}
+
+impl OutputFormatter {
+    fn from_extension(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => OutputFormatter::Csv,
+            Some("jsonl") => OutputFormatter::Json,
+            _ => OutputFormatter::Text,
+        }
+    }
+
+    /// First line of the output, if the format has one
+    pub fn header(self) -> Option<&'static str> {
+        let header = if cfg!(feature = "chrono") {
+            "timestamp,celsius"
+        } else {
+            "timestamp_unix_ms,celsius"
+        };
+        (self == OutputFormatter::Csv).then_some(header)
+    }
+
+    pub fn fmt_reading(self, reading: &TemperatureMeasurement) -> String {
+        let at = reading.received_at.unwrap_or_else(SystemTime::now);
+        match self {
+            OutputFormatter::Text => format!(
+                "{} | {:.1}°C",
+                clock::format(at, "%Y-%m-%d %H:%M:%S"),
+                reading.celsius
+            ),
+            OutputFormatter::Json => format!(
+                "{{\"timestamp\":\"{}\",\"celsius\":{}}}",
+                clock::format(at, RFC3339_MILLIS),
+                reading.celsius
+            ),
+            OutputFormatter::Csv => format!("{},{}", csv_timestamp(reading), reading.celsius),
+        }
+    }
+
+    /// Line for a tick without a new reading: `"celsius":null` in JSON
+    pub fn fmt_missing(self) -> Option<String> {
+        (self == OutputFormatter::Json).then(|| {
+            format!(
+                "{{\"timestamp\":\"{}\",\"celsius\":null}}",
+                clock::format(SystemTime::now(), RFC3339_MILLIS)
+            )
+        })
+    }
+}
+
+/// `2024-01-15T14:32:05.123+01:00`
+const RFC3339_MILLIS: &str = "%Y-%m-%dT%H:%M:%S%.3f%:z";
+
+#[cfg(feature = "chrono")]
+fn csv_timestamp(reading: &TemperatureMeasurement) -> String {
+    reading
+        .to_local_datetime()
+        .unwrap_or_else(chrono::Local::now)
+        .format("%Y-%m-%dT%H:%M:%S%.3f")
+        .to_string()
+}
+
+#[cfg(not(feature = "chrono"))]
+fn csv_timestamp(reading: &TemperatureMeasurement) -> String {
+    clock::unix_ms(reading.received_at.unwrap_or_else(SystemTime::now)).to_string()
+}
+
+/// Pick a sink by `format`, or by the extension of `path`: `.csv`, `.jsonl`
+/// or plain text
+pub fn for_path(
+    path: &Path,
+    format: Option<OutputFormatter>,
+) -> io::Result<Box<dyn OutputSink + Send>> {
+    let writer = BufWriter::new(File::create(path)?);
+    let sink: Box<dyn OutputSink + Send> =
+        match format.unwrap_or_else(|| OutputFormatter::from_extension(path)) {
+            OutputFormatter::Csv => Box::new(CsvSink::new(writer)?),
+            OutputFormatter::Json => Box::new(JsonLinesSink(writer)),
+            OutputFormatter::Text => Box::new(FileSink(writer)),
+        };
+    Ok(sink)
+}
+
+fn write_line(
+    writer: &mut impl Write,
+    format: OutputFormatter,
+    reading: &TemperatureMeasurement,
+) -> io::Result<()> {
+    writeln!(writer, "{}", format.fmt_reading(reading))?;
+    writer.flush()
+}
+
+fn write_missing_line(writer: &mut impl Write, format: OutputFormatter) -> io::Result<()> {
+    let Some(line) = format.fmt_missing() else {
+        return Ok(());
+    };
+    writeln!(writer, "{}", line)?;
+    writer.flush()
+}
+
+/// Readings on stdout
+pub struct StdoutSink(OutputFormatter);
+
+impl StdoutSink {
+    pub fn new(format: OutputFormatter) -> Self {
+        if let Some(header) = format.header() {
+            println!("{}", header);
+        }
+        Self(format)
+    }
+}
+
+impl OutputSink for StdoutSink {
+    fn write_reading(&mut self, reading: &TemperatureMeasurement) -> io::Result<()> {
+        write_line(&mut io::stdout().lock(), self.0, reading)
+    }
+
+    fn write_missing(&mut self) -> io::Result<()> {
+        write_missing_line(&mut io::stdout().lock(), self.0)
+    }
+}
+
+/// Text lines in a file
+pub struct FileSink(pub BufWriter<File>);
+
+impl OutputSink for FileSink {
+    fn write_reading(&mut self, reading: &TemperatureMeasurement) -> io::Result<()> {
+        write_line(&mut self.0, OutputFormatter::Text, reading)
+    }
+}
+
+pub struct CsvSink<W: Write>(W);
+
+impl<W: Write> CsvSink<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        if let Some(header) = OutputFormatter::Csv.header() {
+            writeln!(writer, "{}", header)?;
+        }
+        Ok(Self(writer))
+    }
+}
+
+impl CsvSink<BufWriter<File>> {
+    /// Append to the CSV file at `path`, creating it with a header if absent
+    /// or empty
+    pub fn append(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let is_empty = file.metadata()?.len() == 0;
+
+        let mut writer = BufWriter::new(file);
+        if is_empty {
+            if let Some(header) = OutputFormatter::Csv.header() {
+                writeln!(writer, "{}", header)?;
+                writer.flush()?;
+            }
+        }
+        Ok(Self(writer))
+    }
+}
+
+impl<W: Write> OutputSink for CsvSink<W> {
+    fn write_reading(&mut self, reading: &TemperatureMeasurement) -> io::Result<()> {
+        write_line(&mut self.0, OutputFormatter::Csv, reading)
+    }
+}
+
+pub struct JsonLinesSink<W: Write>(pub W);
+
+impl<W: Write> OutputSink for JsonLinesSink<W> {
+    fn write_reading(&mut self, reading: &TemperatureMeasurement) -> io::Result<()> {
+        write_line(&mut self.0, OutputFormatter::Json, reading)
+    }
+
+    fn write_missing(&mut self) -> io::Result<()> {
+        write_missing_line(&mut self.0, OutputFormatter::Json)
+    }
+}
+
+/// Discards readings, for when only the summary matters
+pub struct NullSink;
+
+impl OutputSink for NullSink {
+    fn write_reading(&mut self, _reading: &TemperatureMeasurement) -> io::Result<()> {
+        Ok(())
+    }
+}
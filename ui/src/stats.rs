@@ -0,0 +1,78 @@
+use std::ops::AddAssign;
+
+use crate::decode::TemperatureMeasurement;
+
+/// Running summary of readings, without keeping them around
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TemperatureStats {
+    pub count: u64,
+    pub sum: f64,
+    sum_sq: f64,
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
+
+impl TemperatureStats {
+    pub fn update(&mut self, celsius: f32) {
+        self.count += 1;
+        self.sum += celsius as f64;
+        self.sum_sq += (celsius as f64).powi(2);
+        self.min = Some(self.min.map_or(celsius, |min| min.min(celsius)));
+        self.max = Some(self.max.map_or(celsius, |max| max.max(celsius)));
+    }
+
+    pub fn mean(&self) -> Option<f32> {
+        (self.count > 0).then(|| (self.sum / self.count as f64) as f32)
+    }
+
+    /// Population standard deviation
+    pub fn std_dev(&self) -> Option<f32> {
+        let mean = self.mean()? as f64;
+        let variance = self.sum_sq / self.count as f64 - mean * mean;
+        Some(variance.max(0.0).sqrt() as f32)
+    }
+}
+
+impl AddAssign<f32> for TemperatureStats {
+    fn add_assign(&mut self, celsius: f32) {
+        self.update(celsius);
+    }
+}
+
+impl AddAssign<TemperatureMeasurement> for TemperatureStats {
+    fn add_assign(&mut self, measurement: TemperatureMeasurement) {
+        self.update(measurement.celsius);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulated_stats_match_the_readings() {
+        let readings: Vec<f32> = (0..1000).map(|i| 20.0 + (i % 37) as f32 * 0.25).collect();
+        let mut stats = TemperatureStats::default();
+        for (i, &celsius) in readings.iter().enumerate() {
+            if i % 2 == 0 {
+                stats += celsius;
+            } else {
+                stats += TemperatureMeasurement::from(celsius);
+            }
+        }
+
+        let sum: f64 = readings.iter().map(|&c| c as f64).sum();
+        let mean = sum / readings.len() as f64;
+        let variance = readings
+            .iter()
+            .map(|&c| (c as f64 - mean).powi(2))
+            .sum::<f64>()
+            / readings.len() as f64;
+        assert_eq!(stats.count, 1000);
+        assert!((stats.sum - sum).abs() < 1e-6);
+        assert_eq!(stats.min, readings.iter().copied().reduce(f32::min));
+        assert_eq!(stats.max, readings.iter().copied().reduce(f32::max));
+        assert!((stats.mean().unwrap() as f64 - mean).abs() < 1e-4);
+        assert!((stats.std_dev().unwrap() as f64 - variance.sqrt()).abs() < 1e-3);
+    }
+}
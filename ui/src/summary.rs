@@ -0,0 +1,61 @@
+use std::future::Future;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::metrics::Metrics;
+
+/// Prints the session summary to stderr when dropped, however `main` ends
+pub struct SessionSummaryPrinter {
+    start: Instant,
+    metrics: Arc<Metrics>,
+}
+
+impl SessionSummaryPrinter {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
+        Self {
+            start: Instant::now(),
+            metrics,
+        }
+    }
+
+    /// Print the summary and exit on Ctrl-C, which would skip the destructor
+    pub fn exit_on_interrupt(&self) -> impl Future<Output = ()> + Send + 'static {
+        let (start, metrics) = (self.start, self.metrics.clone());
+        async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                eprintln!("{}", summary(start, &metrics));
+                std::process::exit(130);
+            }
+        }
+    }
+}
+
+impl Drop for SessionSummaryPrinter {
+    fn drop(&mut self) {
+        eprintln!("{}", summary(self.start, &self.metrics));
+    }
+}
+
+/// `Session summary: 1234 readings over 00:20:34 | Min: 22.1°C ...`
+fn summary(start: Instant, metrics: &Metrics) -> String {
+    let stats = *metrics.readings.lock().unwrap();
+    let elapsed = start.elapsed().as_secs();
+
+    let mut summary = format!(
+        "Session summary: {} readings over {:02}:{:02}:{:02}",
+        stats.count,
+        elapsed / 3600,
+        elapsed / 60 % 60,
+        elapsed % 60
+    );
+    if let (Some(min), Some(max), Some(mean)) = (stats.min, stats.max, stats.mean()) {
+        summary += &format!(" | Min: {:.1}°C Max: {:.1}°C Mean: {:.1}°C", min, max, mean);
+    }
+    summary += &format!(
+        " | {} decode failures | {} alarm events",
+        metrics.decode_failures.load(Ordering::Relaxed),
+        metrics.alarms.load(Ordering::Relaxed)
+    );
+    summary
+}
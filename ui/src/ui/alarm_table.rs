@@ -0,0 +1,182 @@
+use std::cmp::Ordering;
+
+use eframe::egui;
+use egui::Color32;
+use egui_extras::{Column, TableBuilder};
+
+use crate::alarm::{AlarmEvent, AlarmKind};
+use crate::clock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortColumn {
+    Timestamp,
+    Temperature,
+    Kind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// Newest first, like the plain list
+pub const DEFAULT_SORT: (SortColumn, SortDirection) =
+    (SortColumn::Timestamp, SortDirection::Descending);
+
+fn kind_label(kind: AlarmKind) -> (Color32, &'static str) {
+    match kind {
+        AlarmKind::High => (Color32::RED, "High"),
+        AlarmKind::Low => (Color32::BLUE, "Low"),
+    }
+}
+
+fn compare(a: &AlarmEvent, b: &AlarmEvent, column: SortColumn) -> Ordering {
+    match column {
+        SortColumn::Timestamp => a.timestamp.cmp(&b.timestamp),
+        SortColumn::Temperature => a.celsius.total_cmp(&b.celsius),
+        SortColumn::Kind => kind_label(a.kind).1.cmp(kind_label(b.kind).1),
+    }
+}
+
+/// Sort by `column`, reversing the direction if already sorted by it
+fn toggle(sort: &mut (SortColumn, SortDirection), column: SortColumn) {
+    *sort = match *sort {
+        (current, SortDirection::Ascending) if current == column => {
+            (column, SortDirection::Descending)
+        }
+        (current, SortDirection::Descending) if current == column => {
+            (column, SortDirection::Ascending)
+        }
+        _ => (column, SortDirection::Ascending),
+    };
+}
+
+fn sorted<'a>(
+    events: impl Iterator<Item = &'a AlarmEvent>,
+    (column, direction): (SortColumn, SortDirection),
+) -> Vec<&'a AlarmEvent> {
+    let mut events: Vec<_> = events.collect();
+    events.sort_by(|a, b| match direction {
+        SortDirection::Ascending => compare(a, b, column),
+        SortDirection::Descending => compare(b, a, column),
+    });
+    events
+}
+
+/// `events` as a table with resizable columns, sorted by `sort`, which
+/// clicking a column header changes
+pub fn show<'a>(
+    ui: &mut egui::Ui,
+    events: impl Iterator<Item = &'a AlarmEvent>,
+    sort: &mut (SortColumn, SortDirection),
+) {
+    let rows = sorted(events, *sort);
+    let mut clicked = None;
+
+    TableBuilder::new(ui)
+        .striped(true)
+        .resizable(true)
+        .max_scroll_height(200.0)
+        .column(Column::auto().at_least(60.0))
+        .column(Column::auto().at_least(60.0))
+        .column(Column::remainder())
+        .header(20.0, |mut header| {
+            for (column, title) in [
+                (SortColumn::Timestamp, "Timestamp"),
+                (SortColumn::Temperature, "Temperature"),
+                (SortColumn::Kind, "Type"),
+            ] {
+                header.col(|ui| {
+                    let arrow = match *sort {
+                        (current, SortDirection::Ascending) if current == column => " ⬆",
+                        (current, SortDirection::Descending) if current == column => " ⬇",
+                        _ => "",
+                    };
+                    if ui
+                        .selectable_label(sort.0 == column, format!("{}{}", title, arrow))
+                        .clicked()
+                    {
+                        clicked = Some(column);
+                    }
+                });
+            }
+        })
+        .body(|mut body| {
+            for alarm in rows {
+                body.row(18.0, |mut row| {
+                    row.col(|ui| {
+                        ui.label(clock::format(alarm.timestamp, "%H:%M:%S"));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.1}°C", alarm.celsius));
+                    });
+                    row.col(|ui| {
+                        let (color, kind) = kind_label(alarm.kind);
+                        ui.colored_label(color, kind);
+                    });
+                });
+            }
+        });
+
+    if let Some(column) = clicked {
+        toggle(sort, column);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    fn alarm(secs: u64, celsius: f32, kind: AlarmKind) -> AlarmEvent {
+        AlarmEvent {
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            celsius,
+            kind,
+        }
+    }
+
+    #[test]
+    fn header_clicks_toggle_the_direction() {
+        let mut sort = DEFAULT_SORT;
+        toggle(&mut sort, SortColumn::Timestamp);
+        assert_eq!(sort, (SortColumn::Timestamp, SortDirection::Ascending));
+        toggle(&mut sort, SortColumn::Timestamp);
+        assert_eq!(sort, (SortColumn::Timestamp, SortDirection::Descending));
+        toggle(&mut sort, SortColumn::Temperature);
+        assert_eq!(sort, (SortColumn::Temperature, SortDirection::Ascending));
+    }
+
+    #[test]
+    fn sorts_by_the_chosen_column() {
+        let events = [
+            alarm(1, 31.0, AlarmKind::High),
+            alarm(2, 4.0, AlarmKind::Low),
+            alarm(3, 35.0, AlarmKind::High),
+        ];
+        let seconds = |sort| -> Vec<u64> {
+            sorted(events.iter(), sort)
+                .iter()
+                .map(|a| {
+                    a.timestamp
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs()
+                })
+                .collect()
+        };
+
+        assert_eq!(seconds(DEFAULT_SORT), [3, 2, 1]);
+        assert_eq!(
+            seconds((SortColumn::Temperature, SortDirection::Ascending)),
+            [2, 1, 3]
+        );
+        // stable, so the highs keep their order
+        assert_eq!(
+            seconds((SortColumn::Kind, SortDirection::Ascending)),
+            [1, 3, 2]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use egui::epaint::{ClippedShape, Primitive};
+use sysinfo::{Pid, ProcessRefreshKind, System};
+
+/// How often the memory usage and vertex count are read again
+const REFRESH: Duration = Duration::from_secs(1);
+
+/// Frame rate, memory usage and vertex count drawn over the chart with `--debug`
+pub struct DebugHud {
+    system: System,
+    pid: Option<Pid>,
+    /// Resident set size in bytes
+    rss: Option<u64>,
+    /// Vertices egui tessellated the frame into, up to the HUD itself
+    vertices: usize,
+    refreshed: Option<Instant>,
+}
+
+impl Default for DebugHud {
+    fn default() -> Self {
+        Self {
+            system: System::new(),
+            pid: sysinfo::get_current_pid().ok(),
+            rss: None,
+            vertices: 0,
+            refreshed: None,
+        }
+    }
+}
+
+impl DebugHud {
+    fn refresh(&mut self, ctx: &egui::Context) {
+        if self.refreshed.is_some_and(|at| at.elapsed() < REFRESH) {
+            return;
+        }
+
+        if let Some(pid) = self.pid {
+            self.system
+                .refresh_process_specifics(pid, ProcessRefreshKind::new().with_memory());
+            self.rss = self.system.process(pid).map(|p| p.memory());
+        }
+        self.vertices = count_vertices(ctx);
+        self.refreshed = Some(Instant::now());
+    }
+
+    /// Draw into the bottom right corner of `rect`
+    pub fn show(&mut self, ui: &egui::Ui, rect: egui::Rect) {
+        self.refresh(ui.ctx());
+
+        // egui only repaints on input, so this is the rate of the last frames drawn
+        let dt = ui.input(|i| i.unstable_dt);
+        let fps = if dt > 0.0 { 1.0 / dt } else { 0.0 };
+        let rss = self.rss.map_or("RSS n/a".to_owned(), |bytes| {
+            format!("RSS {:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+        });
+
+        ui.painter().text(
+            rect.right_bottom() + egui::vec2(-8.0, -8.0),
+            egui::Align2::RIGHT_BOTTOM,
+            format!("{:.0} FPS | {} | {} vertices", fps, rss, self.vertices),
+            egui::FontId::monospace(12.0),
+            ui.visuals().weak_text_color(),
+        );
+    }
+}
+
+/// Tessellate what was painted so far this frame, a second time, and count
+/// the vertices
+fn count_vertices(ctx: &egui::Context) -> usize {
+    let layers: Vec<egui::LayerId> = ctx.memory(|m| m.layer_ids().collect());
+    let shapes: Vec<ClippedShape> = ctx.graphics(|g| {
+        layers
+            .iter()
+            .filter_map(|layer| g.get(*layer))
+            .flat_map(|list| list.all_entries().cloned())
+            .collect()
+    });
+    ctx.tessellate(shapes, ctx.pixels_per_point())
+        .iter()
+        .map(|clipped| match &clipped.primitive {
+            Primitive::Mesh(mesh) => mesh.vertices.len(),
+            Primitive::Callback(_) => 0,
+        })
+        .sum()
+}
@@ -0,0 +1,1081 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use eframe::egui;
+use egui::Color32;
+use egui_plot::{
+    uniform_grid_spacer, HLine, Legend, Line, LineStyle, LineStyle::Solid, MarkerShape, Plot,
+    PlotPoint, PlotPoints, Points, Polygon, Text, VLine,
+};
+use log::{info, warn};
+use tokio::sync::Notify;
+
+#[cfg(feature = "table-ui")]
+mod alarm_table;
+mod hud;
+
+#[cfg(not(feature = "table-ui"))]
+use crate::alarm::AlarmKind;
+use crate::alert_sound::TemperatureAlertSoundPlayer;
+use crate::ble::{ConnectionState, Event, StreamStats};
+use crate::chart::{self, ChartType, SensorStyle};
+use crate::clock;
+use crate::config::Config;
+use crate::event_log::LogEvent;
+use crate::file_dialog::FileDialog;
+use crate::flush::Flusher;
+use crate::metrics::Metrics;
+use crate::overlay::{self, Overlay, Reference};
+use crate::session::TemperatureSession;
+use crate::session_file;
+use crate::signal::RssiHistory;
+#[cfg(feature = "table-ui")]
+use alarm_table::{SortColumn, SortDirection};
+use hud::DebugHud;
+
+/// Number of readings the latency maximum is taken over
+const LATENCY_WINDOW: usize = 100;
+
+/// Number of readings the notification rate is averaged over
+const RATE_WINDOW: usize = 10;
+
+/// Time the trend arrow is computed over
+const TREND_WINDOW: Duration = Duration::from_secs(30);
+
+/// Change in °C per minute beyond which the temperature counts as rising or falling
+const TREND_THRESHOLD: f32 = 0.2;
+
+/// Below this many readings per second the rate is drawn as a warning
+const SLOW_RATE_HZ: f32 = 0.1;
+
+pub struct UI {
+    /// Readings and state changes from the sensor task
+    events: Receiver<Event>,
+    current_session: TemperatureSession,
+    /// Kept for comparison after a reconnect
+    previous_session: Option<TemperatureSession>,
+    flusher: Option<Flusher>,
+    config: Config,
+    config_path: PathBuf,
+    sensor_name: Option<String>,
+    sensor_label: Option<String>,
+    /// Recent signal strength per sensor name
+    rssi: HashMap<String, RssiHistory>,
+    show_band: bool,
+    show_thresholds: bool,
+    /// Vertical lines where alarms fired
+    show_alarm_markers: bool,
+    start: Instant,
+    start_time: SystemTime,
+    grid_auto: bool,
+    grid_x_step: f64,
+    grid_y_step: f64,
+    overlays: Vec<Overlay>,
+    /// Files of earlier sessions the flusher wrote, offered as overlays
+    flushed_files: Vec<PathBuf>,
+    pub reference: Option<Reference>,
+    overlay_dialog: FileDialog,
+    pub debug: bool,
+    /// Readings to wait for before the chart is shown
+    pub min_readings: usize,
+    /// Counts down from `min_readings` as readings arrive
+    pub warmup_remaining: usize,
+    hud: DebugHud,
+    notify_instant: Arc<Mutex<Option<Instant>>>,
+    metrics: Arc<Metrics>,
+    alert_sound: TemperatureAlertSoundPlayer,
+    /// Notification to repaint delay of the most recent readings
+    latencies: VecDeque<Duration>,
+    connection_state: ConnectionState,
+    /// Last counts of the notification stream, sent periodically
+    stream_stats: Option<StreamStats>,
+    /// Restarts the sensor task after an error
+    pub retry: Arc<Notify>,
+    show_side_panel: bool,
+    /// Freezes the chart; readings are held in `pending` meanwhile
+    paused: bool,
+    /// Readings received while paused, recorded on resume
+    pending: VecDeque<(Instant, f32)>,
+    /// Keep the latest reading in view; off while the user drags or zooms
+    follow_mode: bool,
+    /// Column and direction the alarm table is sorted by
+    #[cfg(feature = "table-ui")]
+    alarm_sort: (SortColumn, SortDirection),
+}
+
+impl UI {
+    pub fn new(
+        events: Receiver<Event>,
+        config: Config,
+        config_path: PathBuf,
+        notify_instant: Arc<Mutex<Option<Instant>>>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        Self {
+            current_session: TemperatureSession::new(config.effective_history_len()),
+            previous_session: None,
+            alert_sound: TemperatureAlertSoundPlayer::new(config.alert_sound.clone()),
+            events,
+            flusher: config.flusher(),
+            flushed_files: config.flushed_files(),
+            config,
+            config_path,
+            sensor_name: None,
+            sensor_label: None,
+            rssi: HashMap::new(),
+            show_band: true,
+            show_thresholds: true,
+            show_alarm_markers: true,
+            start: Instant::now(),
+            start_time: SystemTime::now(),
+            grid_auto: true,
+            grid_x_step: 60.0,
+            grid_y_step: 1.0,
+            overlays: Vec::new(),
+            reference: None,
+            overlay_dialog: FileDialog::new("Load Overlay"),
+            debug: false,
+            min_readings: 1,
+            warmup_remaining: 1,
+            hud: DebugHud::default(),
+            notify_instant,
+            metrics,
+            latencies: VecDeque::with_capacity(LATENCY_WINDOW),
+            connection_state: ConnectionState::Idle,
+            stream_stats: None,
+            retry: Arc::new(Notify::new()),
+            show_side_panel: true,
+            paused: false,
+            pending: VecDeque::new(),
+            follow_mode: true,
+            #[cfg(feature = "table-ui")]
+            alarm_sort: alarm_table::DEFAULT_SORT,
+        }
+    }
+
+    fn record(&mut self, at: Instant, temp: f32) {
+        let alarm = self.current_session.record(
+            at,
+            temp,
+            self.config.smoothing_alpha as f32,
+            self.config.alert_high_celsius,
+            self.config.alert_low_celsius,
+            self.config.alert_hysteresis_celsius,
+        );
+        if let Some(kind) = alarm {
+            self.metrics.alarms.fetch_add(1, Ordering::Relaxed);
+            self.alert_sound.on_alarm(kind);
+        }
+    }
+
+    fn record_latency(&mut self) {
+        let Some(notified) = *self.notify_instant.lock().unwrap() else {
+            return;
+        };
+
+        if self.latencies.len() >= LATENCY_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(notified.elapsed());
+    }
+
+    fn debug_panel(&self, ui: &mut egui::Ui) {
+        ui.collapsing("Debug", |ui| {
+            if let (Some(last), Some(max)) = (self.latencies.back(), self.latencies.iter().max()) {
+                ui.label(format!(
+                    "Latency: {} ms (max {} ms)",
+                    last.as_millis(),
+                    max.as_millis()
+                ));
+            }
+
+            ui.collapsing("Runtime Info", |ui| {
+                let tasks = tokio::runtime::Handle::current()
+                    .metrics()
+                    .num_alive_tasks();
+                let metrics = &self.metrics;
+
+                ui.label(format!("Tokio tasks: {}", tasks));
+                ui.label(format!(
+                    "BLE connections: {}",
+                    metrics.connections.load(Ordering::Relaxed)
+                ));
+                ui.label(format!("Sensors with readings: {}", self.sensor_count()));
+                let notifications = metrics.notifications.load(Ordering::Relaxed);
+                let decoded = metrics.decoded.load(Ordering::Relaxed);
+                let failures = metrics.decode_failures.load(Ordering::Relaxed);
+                ui.label(format!("Notifications: {}", notifications));
+                ui.label(format!(
+                    "Bytes received: {}",
+                    format_bytes(metrics.bytes_received.load(Ordering::Relaxed))
+                ));
+                ui.label(format!(
+                    "Decoded: {} / {} ({:.2}%)",
+                    decoded,
+                    notifications,
+                    decoded as f64 * 100.0 / notifications.max(1) as f64
+                ));
+                ui.label(format!(
+                    "Decode failures: {} / {} ({:.2}%)",
+                    failures,
+                    notifications,
+                    failures as f64 * 100.0 / notifications.max(1) as f64
+                ));
+                ui.label(format!(
+                    "Queue depth: {}",
+                    metrics.queue_depth.load(Ordering::Relaxed)
+                ));
+            });
+
+            if let Some(stats) = &self.stream_stats {
+                ui.collapsing("Notification Stream", |ui| {
+                    ui.label(format!("Items: {}", stats.total));
+                    ui.label(format!("Empty: {}", stats.empty));
+                    ui.label(format!("Wrong length: {}", stats.wrong_length));
+                    ui.label(format!(
+                        "Decoded: {} ({:.2}%)",
+                        stats.decoded,
+                        stats.success_rate()
+                    ));
+                });
+            }
+        });
+    }
+
+    /// `"Kitchen (SensorXYZ)"` if a location matches the sensor, otherwise its name
+    fn label_for(&self, name: &str, address: &str) -> String {
+        let name = if name.is_empty() { address } else { name };
+
+        self.config
+            .sensor_locations
+            .iter()
+            .find(|(filter, _)| name.contains(filter.as_str()) || address == filter.as_str())
+            .map(|(_, location)| format!("{} ({})", location, name))
+            .unwrap_or_else(|| name.to_owned())
+    }
+
+    /// Sensors with readings in the current session. There is a single
+    /// session until several sensors can be connected at once
+    fn sensor_count(&self) -> usize {
+        self.all_current_temps().len()
+    }
+
+    /// Latest reading of each sensor
+    fn all_current_temps(&self) -> Vec<f32> {
+        let latest = self.current_session.history.iter().next_back();
+        latest.map(|(_, celsius)| *celsius).into_iter().collect()
+    }
+
+    /// Write settings changed in the UI back to the config file
+    fn persist(&self, keys: &[&str]) {
+        if let Err(e) = self.config.save_keys(&self.config_path, keys) {
+            warn!("failed to save {}: {}", self.config_path.display(), e);
+        }
+    }
+
+    /// Write the in-memory readings to `session_<start>.json` in the flush directory
+    fn save_session(&self) {
+        let start = self
+            .current_session
+            .start_time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let path = self
+            .config
+            .flush_dir
+            .join(format!("session_{}.json", start));
+
+        let label = self.sensor_label.as_deref().unwrap_or_default();
+        let records = session_file::from_readings(&self.current_session.history, label);
+        match session_file::save(&path, &records) {
+            Ok(()) => info!("saved {} readings to {}", records.len(), path.display()),
+            Err(e) => warn!("failed to save {}: {}", path.display(), e),
+        }
+    }
+
+    fn load_overlay(&mut self, path: &Path) {
+        match Overlay::load(path) {
+            Ok(overlay) => self.overlays.push(overlay),
+            Err(e) => warn!("failed to load overlay {}: {}", path.display(), e),
+        }
+    }
+
+    fn settings_panel(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.show_band, "Show min/max band");
+
+        let mut history_len = self.current_session.history.max_len();
+        let max = self.config.max_records();
+        let slider = egui::Slider::new(&mut history_len, 10.min(max)..=max)
+            .logarithmic(true)
+            .text("History length");
+        if ui.add(slider).changed() {
+            self.current_session.history.set_max_len(history_len);
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.checkbox(&mut self.show_thresholds, "Show thresholds");
+            threshold_edit(ui, "High", &mut self.config.alert_high_celsius, 30.0);
+            threshold_edit(ui, "Low", &mut self.config.alert_low_celsius, 15.0);
+        });
+        ui.checkbox(&mut self.show_alarm_markers, "Show alarm markers");
+
+        if ui.button("Save Session").clicked() {
+            self.save_session();
+        }
+
+        let mut flushed = None;
+        ui.horizontal_wrapped(|ui| {
+            let can_load = self.overlays.len() < overlay::MAX_OVERLAYS;
+            if ui
+                .add_enabled(can_load, egui::Button::new("Load Overlay"))
+                .clicked()
+            {
+                self.overlay_dialog.open();
+            }
+            if !self.flushed_files.is_empty() {
+                ui.add_enabled_ui(can_load, |ui| {
+                    ui.menu_button("Flushed Files", |ui| {
+                        for path in &self.flushed_files {
+                            let name = path.file_name().unwrap_or_default().to_string_lossy();
+                            if ui.button(name).clicked() {
+                                flushed = Some(path.clone());
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+            }
+
+            let mut removed = None;
+            for (i, overlay) in self.overlays.iter().enumerate() {
+                ui.label(&overlay.name);
+                if ui.small_button("×").clicked() {
+                    removed = Some(i);
+                }
+            }
+            if let Some(i) = removed {
+                self.overlays.remove(i);
+            }
+        });
+        if let Some(path) = flushed {
+            self.load_overlay(&path);
+        }
+
+        if let Some(reference) = &mut self.reference {
+            ui.horizontal(|ui| {
+                ui.label("Reference offset:");
+                ui.add(
+                    egui::DragValue::new(&mut reference.offset_secs)
+                        .speed(10.0)
+                        .suffix(" s"),
+                );
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Grid:");
+            ui.checkbox(&mut self.grid_auto, "Auto");
+            ui.add_enabled_ui(!self.grid_auto, |ui| {
+                ui.add(
+                    egui::DragValue::new(&mut self.grid_y_step)
+                        .clamp_range(0.1..=10.0)
+                        .speed(0.1)
+                        .suffix(" °C"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut self.grid_x_step)
+                        .clamp_range(1.0..=3600.0)
+                        .speed(1.0)
+                        .suffix(" s"),
+                );
+            });
+        });
+
+        if let Some(name) = &self.sensor_name {
+            let style = self.config.sensor_styles.get_mut(name);
+            if let Some(style) = style {
+                let changed = ui
+                    .horizontal(|ui| {
+                        ui.label(name);
+                        let color =
+                            egui::color_picker::color_edit_button_srgb(ui, &mut style.color);
+                        let width = ui.add(
+                            egui::DragValue::new(&mut style.width)
+                                .clamp_range(0.5..=10.0)
+                                .speed(0.1)
+                                .suffix(" px"),
+                        );
+                        color.changed() || width.changed()
+                    })
+                    .inner;
+                if changed {
+                    self.persist(&["sensor_styles"]);
+                }
+            }
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Chart type:");
+            let chart_type = &mut self.config.chart_type;
+            let changed = ui
+                .radio_value(chart_type, ChartType::Line, "Line")
+                .changed()
+                | ui.radio_value(chart_type, ChartType::Scatter, "Scatter")
+                    .changed()
+                | ui.radio_value(chart_type, ChartType::Step, "Step")
+                    .changed();
+            if changed {
+                self.persist(&["chart_type"]);
+            }
+        });
+
+        if let Some(info) = &self.current_session.sensor_info {
+            ui.collapsing("Device Info", |ui| {
+                egui::Grid::new("device_info").striped(true).show(ui, |ui| {
+                    for (label, value) in info.fields() {
+                        ui.label(label);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+    }
+
+    fn stats_panel(&self, ui: &mut egui::Ui) {
+        let session = &self.current_session;
+        let started = clock::format(session.start_time, "%H:%M:%S");
+        ui.label(format!("Session started: {}", started));
+
+        let (Some((_, current)), Some(mean), Some(min), Some(max)) = (
+            session.history.iter().next_back(),
+            session.stats.mean(),
+            session.stats.min,
+            session.stats.max,
+        ) else {
+            ui.label("No readings yet");
+            return;
+        };
+
+        let unit = self.config.display_unit;
+        ui.horizontal(|ui| {
+            ui.label(format!("Current: {}", unit.format(*current)));
+            if let Some(rate) = session.history.rate_per_minute(TREND_WINDOW) {
+                ui.label(trend_arrow(rate))
+                    .on_hover_text(format!("{:+.2}°C/min", rate));
+            }
+        });
+        if let (Some(smoothed), true) = (session.smoothed, self.config.smoothing_alpha < 1.0) {
+            ui.label(format!("Smoothed: {}", unit.format(smoothed)));
+        }
+        ui.label(format!("Average: {}", unit.format(mean)));
+        ui.label(format!("Min: {}", unit.format(min)));
+        ui.label(format!("Max: {}", unit.format(max)));
+        ui.label(format!("Readings: {}", session.stats.count));
+        if let (Some(mean), Some(std_dev)) = (session.history.mean(), session.history.std_dev()) {
+            ui.label(format!(
+                "Last {}: {:.1} ± {:.2}{}",
+                session.history.len(),
+                unit.convert(mean),
+                unit.convert_difference(std_dev),
+                unit.symbol()
+            ));
+        }
+    }
+
+    fn event_log_panel(&self, ui: &mut egui::Ui) {
+        if ui.button("Copy to Clipboard").clicked() {
+            let text: Vec<String> = self.current_session.event_log.lines().collect();
+            ui.output_mut(|o| o.copied_text = text.join("\n"));
+        }
+
+        egui::ScrollArea::vertical()
+            .id_source("event_log")
+            .max_height(200.0)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in self.current_session.event_log.lines() {
+                    ui.monospace(line);
+                }
+            });
+    }
+
+    #[cfg(feature = "table-ui")]
+    fn alarm_log_panel(&mut self, ui: &mut egui::Ui) {
+        if self.current_session.alarm_log.events.is_empty() {
+            ui.label("No alarms");
+            return;
+        }
+
+        let events = self.current_session.alarm_log.events.iter();
+        alarm_table::show(ui, events, &mut self.alarm_sort);
+    }
+
+    #[cfg(not(feature = "table-ui"))]
+    fn alarm_log_panel(&self, ui: &mut egui::Ui) {
+        if self.current_session.alarm_log.events.is_empty() {
+            ui.label("No alarms");
+            return;
+        }
+
+        for alarm in self.current_session.alarm_log.events.iter().rev() {
+            let at = clock::format(alarm.timestamp, "%H:%M:%S");
+            let (color, kind) = match alarm.kind {
+                AlarmKind::High => (Color32::RED, "High"),
+                AlarmKind::Low => (Color32::BLUE, "Low"),
+            };
+            ui.colored_label(color, format!("{} {} {:.1}°C", at, kind, alarm.celsius));
+        }
+    }
+}
+
+impl eframe::App for UI {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // receive temperature
+        while let Ok(event) = self.events.try_recv() {
+            self.metrics.queue_depth.fetch_sub(1, Ordering::Relaxed);
+            match event {
+                Event::Connected { name, address } => {
+                    if !self.current_session.history.is_empty() {
+                        let session =
+                            TemperatureSession::new(self.current_session.history.max_len());
+                        self.previous_session =
+                            Some(std::mem::replace(&mut self.current_session, session));
+                    }
+                    self.current_session.event_log.push(LogEvent::Connected {
+                        name: name.clone(),
+                        address: address.clone(),
+                    });
+                    let label = self.label_for(&name, &address);
+                    self.connection_state = ConnectionState::Connected {
+                        name: label.clone(),
+                    };
+                    self.sensor_label = Some(label);
+
+                    let styles = &mut self.config.sensor_styles;
+                    let n = styles.len();
+                    styles
+                        .entry(name.clone())
+                        .or_insert_with(|| SensorStyle::nth(n));
+                    self.sensor_name = Some(name);
+                }
+                Event::Reading(reading) => {
+                    let temp = reading.celsius;
+                    self.warmup_remaining = self.warmup_remaining.saturating_sub(1);
+                    if self.paused {
+                        // older ones would fall out of the history on resume anyway
+                        if self.pending.len() >= self.current_session.history.max_len() {
+                            self.pending.pop_front();
+                        }
+                        self.pending.push_back((Instant::now(), temp));
+                    } else {
+                        self.record(Instant::now(), temp);
+                    }
+                    self.record_latency();
+                }
+                Event::DeviceInfo(info) => self.current_session.sensor_info = Some(info),
+                Event::Rssi(rssi) => {
+                    if let Some(name) = &self.sensor_name {
+                        self.rssi.entry(name.clone()).or_default().push(rssi);
+                    }
+                }
+                Event::Log(event) => self.current_session.event_log.push(event),
+                Event::State(state) => self.connection_state = state,
+                Event::StreamStats(stats) => self.stream_stats = Some(stats),
+                Event::Error(e) => self.connection_state = ConnectionState::Error(e),
+            }
+        }
+
+        if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::Space)) {
+            self.paused = !self.paused;
+            if !self.paused {
+                while let Some((at, temp)) = self.pending.pop_front() {
+                    self.record(at, temp);
+                }
+            }
+        }
+
+        if let Some(flusher) = &mut self.flusher {
+            let label = self.sensor_label.as_deref().unwrap_or("celsius");
+            flusher.poll(&mut self.current_session.history, label);
+        }
+
+        let mut retry = false;
+        egui::TopBottomPanel::bottom("status").show(ctx, |ui| {
+            ui.horizontal(|ui| match &self.connection_state {
+                ConnectionState::Error(_) => {
+                    ui.colored_label(Color32::RED, format!("⚠ {}", self.connection_state));
+                    retry = ui.button("Retry").clicked();
+                }
+                state => {
+                    ui.label(state.to_string());
+                }
+            });
+        });
+
+        if retry {
+            self.connection_state = ConnectionState::Idle;
+            self.retry.notify_one();
+        }
+        // keeps the countdown moving
+        if matches!(self.connection_state, ConnectionState::Reconnecting { .. }) {
+            ctx.request_repaint_after(Duration::from_secs(1));
+        }
+
+        if self.show_side_panel {
+            egui::SidePanel::right("settings")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        egui::CollapsingHeader::new("Settings")
+                            .default_open(true)
+                            .show(ui, |ui| self.settings_panel(ui));
+                        egui::CollapsingHeader::new("Statistics")
+                            .default_open(true)
+                            .show(ui, |ui| self.stats_panel(ui));
+                        egui::CollapsingHeader::new("Alarm log")
+                            .default_open(true)
+                            .show(ui, |ui| self.alarm_log_panel(ui));
+                        egui::CollapsingHeader::new("Event log")
+                            .show(ui, |ui| self.event_log_panel(ui));
+
+                        if self.debug {
+                            self.debug_panel(ui);
+                        }
+                    });
+                });
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if matches!(self.connection_state, ConnectionState::NoAdapter) {
+                if no_adapter_banner(ui) {
+                    self.connection_state = ConnectionState::Idle;
+                    self.retry.notify_one();
+                }
+                ui.add_space(4.0);
+            }
+
+            ui.horizontal(|ui| {
+                ui.heading("BLE Temperature Monitor");
+                if let Some(rssi) = self.sensor_name.as_ref().and_then(|n| self.rssi.get(n)) {
+                    signal_bar(ui, rssi);
+                }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.toggle_value(&mut self.show_side_panel, "⚙ Settings");
+                    let mode = if self.follow_mode {
+                        "📌 Follow"
+                    } else {
+                        "🔍 Browse"
+                    };
+                    ui.toggle_value(&mut self.follow_mode, mode);
+                });
+            });
+
+            if self.warmup_remaining > 0 {
+                let received = self.min_readings.saturating_sub(self.warmup_remaining);
+                ui.vertical_centered(|ui| {
+                    ui.add_space(ui.available_height() / 3.0);
+                    ui.add(egui::Spinner::new());
+                    ui.label(format!(
+                        "Warming up… ({}/{} readings)",
+                        received, self.min_readings
+                    ));
+                });
+                return;
+            }
+
+            let range = self.config.temperature_range_celsius();
+            let mut plot = Plot::new("temperature")
+                .legend(Legend::default())
+                .include_y(*range.start())
+                .include_y(*range.end())
+                .show_axes([false, true])
+                .show_x(false)
+                .show_grid(true);
+            if self.follow_mode {
+                plot = plot.reset();
+            }
+            // keep every sensor's current value in view
+            for celsius in self.all_current_temps() {
+                plot = plot.include_y(celsius);
+            }
+
+            if !self.grid_auto {
+                let (x_step, y_step) = (self.grid_x_step, self.grid_y_step);
+                plot = plot
+                    .x_grid_spacer(uniform_grid_spacer(move |_| {
+                        [x_step, x_step * 5.0, x_step * 10.0]
+                    }))
+                    .y_grid_spacer(uniform_grid_spacer(move |_| {
+                        [y_step, y_step * 5.0, y_step * 10.0]
+                    }));
+            }
+
+            let style = self
+                .sensor_name
+                .as_ref()
+                .and_then(|name| self.config.sensor_styles.get(name))
+                .copied()
+                .unwrap_or(SensorStyle::nth(0));
+
+            // x is seconds since the UI started
+            let points: Vec<[f64; 2]> = self
+                .current_session
+                .history
+                .iter()
+                .map(|(t, x)| [t.duration_since(self.start).as_secs_f64(), *x as f64])
+                .collect();
+
+            let response = plot.show(ui, |plot_ui| {
+                let hovered = plot_ui.pointer_coordinate().and_then(|cursor| {
+                    let points: Vec<(f64, f64)> = points.iter().map(|&[x, y]| (x, y)).collect();
+                    chart::find_nearest(&points, cursor)
+                });
+
+                if let (true, Some(min), Some(max), Some(first), Some(last)) = (
+                    self.show_band,
+                    self.current_session.history.min(),
+                    self.current_session.history.max(),
+                    points.first(),
+                    points.last(),
+                ) {
+                    let (start, end) = (first[0], last[0]);
+                    let (min, max) = (min as f64, max as f64);
+                    let [r, g, b] = style.color;
+
+                    let band =
+                        Polygon::new(vec![[start, min], [end, min], [end, max], [start, max]])
+                            .fill_color(Color32::from_rgba_unmultiplied(r, g, b, 60))
+                            .stroke(egui::Stroke::NONE)
+                            .name("Min/max");
+
+                    plot_ui.polygon(band);
+                }
+
+                if self.show_thresholds {
+                    let right = plot_ui.plot_bounds().max()[0];
+                    let thresholds = [
+                        (
+                            self.config.alert_high_celsius,
+                            Color32::RED,
+                            "High threshold",
+                        ),
+                        (
+                            self.config.alert_low_celsius,
+                            Color32::BLUE,
+                            "Low threshold",
+                        ),
+                    ];
+
+                    for (threshold, color, name) in thresholds {
+                        let Some(threshold) = threshold else {
+                            continue;
+                        };
+
+                        let line = HLine::new(threshold)
+                            .color(color)
+                            .style(LineStyle::Dashed { length: 5.0 })
+                            .name(name);
+                        plot_ui.hline(line);
+
+                        let label = Text::new(
+                            PlotPoint::new(right, threshold),
+                            format!("{:.1}°C", threshold),
+                        )
+                        .color(color)
+                        .anchor(egui::Align2::RIGHT_BOTTOM);
+                        plot_ui.text(label);
+                    }
+                }
+
+                for (i, overlay) in self.overlays.iter().enumerate() {
+                    let line = Line::new(PlotPoints::from(overlay.points.clone()))
+                        .color(Color32::GRAY)
+                        .style(overlay::line_style(i))
+                        .name(&overlay.name);
+                    plot_ui.line(line);
+                }
+
+                if let Some(reference) = &self.reference {
+                    let chart_start_ms = clock::unix_ms(self.start_time) as u64;
+                    let points: Vec<[f64; 2]> = reference
+                        .points
+                        .iter()
+                        .map(|[x, y]| [x + reference.offset_secs, *y])
+                        .collect();
+
+                    let line = Line::new(PlotPoints::from(points))
+                        .color(Color32::GRAY)
+                        .name(reference.name(chart_start_ms));
+                    plot_ui.line(line);
+                }
+
+                // the previous session starts where the current one does
+                if let Some(previous) = &self.previous_session {
+                    let readings = &previous.history;
+                    if let Some((first, _)) = readings.iter().next() {
+                        let offset = points.first().map_or(0.0, |p| p[0]);
+                        let previous_points: Vec<[f64; 2]> = readings
+                            .iter()
+                            .map(|(t, x)| {
+                                [t.duration_since(*first).as_secs_f64() + offset, *x as f64]
+                            })
+                            .collect();
+
+                        let line = Line::new(PlotPoints::from(previous_points))
+                            .color(Color32::LIGHT_GRAY)
+                            .name("Previous session");
+                        plot_ui.line(line);
+                    }
+                }
+
+                let name = self.sensor_label.as_deref().unwrap_or("Temperature (°C)");
+
+                match self.config.chart_type {
+                    ChartType::Line | ChartType::Step => {
+                        let points = if self.config.chart_type == ChartType::Step {
+                            chart::to_steps(&points)
+                        } else {
+                            points
+                        };
+
+                        let line = Line::new(PlotPoints::from(points))
+                            .color(style.color32())
+                            .width(style.width)
+                            .style(Solid)
+                            .highlight(true)
+                            .name(name);
+
+                        plot_ui.line(line);
+                    }
+                    ChartType::Scatter => {
+                        let markers = Points::new(PlotPoints::from(points))
+                            .shape(MarkerShape::Diamond)
+                            .color(style.color32())
+                            .radius(style.width * 2.0)
+                            .filled(true)
+                            .name(name);
+
+                        plot_ui.points(markers);
+                    }
+                }
+
+                if self.show_alarm_markers {
+                    for alarm in &self.current_session.alarm_log.events {
+                        // same origin as the readings, which the session may have started after
+                        let Ok(since_start) = alarm.timestamp.duration_since(self.start_time)
+                        else {
+                            continue;
+                        };
+                        let line = VLine::new(since_start.as_secs_f64())
+                            .color(Color32::RED)
+                            .name("Alarm");
+                        plot_ui.vline(line);
+                    }
+                }
+
+                hovered
+            });
+
+            if let (Some((x, y)), true) = (response.inner, response.response.hovered()) {
+                let at = self.start_time + Duration::from_secs_f64(x);
+                let at = clock::format(at, "%H:%M:%S");
+
+                egui::show_tooltip_at_pointer(ctx, egui::Id::new("reading_tooltip"), |ui| {
+                    ui.label(format!("{:.1}°C at {}", y, at));
+                });
+            }
+
+            if let Some(rate) = self.current_session.history.rate_hz(RATE_WINDOW) {
+                let color = if rate < SLOW_RATE_HZ {
+                    Color32::from_rgb(255, 140, 0)
+                } else {
+                    ui.visuals().weak_text_color()
+                };
+                ui.painter().text(
+                    response.response.rect.left_top() + egui::vec2(8.0, 8.0),
+                    egui::Align2::LEFT_TOP,
+                    format!("Rate: {:.1} Hz", rate),
+                    egui::FontId::proportional(12.0),
+                    color,
+                );
+            }
+
+            // moving the view by hand leaves follow mode
+            let scrolled = response.response.hovered()
+                && ui.input(|i| i.raw_scroll_delta != egui::Vec2::ZERO || i.zoom_delta() != 1.0);
+            if response.response.dragged() || scrolled {
+                self.follow_mode = false;
+            }
+
+            if self.paused {
+                ui.painter().text(
+                    response.response.rect.center_top() + egui::vec2(0.0, 8.0),
+                    egui::Align2::CENTER_TOP,
+                    format!("⏸ PAUSED ({} pending)", self.pending.len()),
+                    egui::FontId::proportional(16.0),
+                    Color32::from_rgb(255, 140, 0),
+                );
+            }
+
+            if self.debug {
+                self.hud.show(ui, response.response.rect);
+            }
+        });
+
+        if let Some(path) = self.overlay_dialog.show(ctx) {
+            self.load_overlay(&path);
+        }
+    }
+}
+
+/// Five bars filled up to the signal level, details on hover
+fn signal_bar(ui: &mut egui::Ui, rssi: &RssiHistory) {
+    let (Some(level), Some(current), Some(quality), Some(stability), Some(std_dev)) = (
+        rssi.level(),
+        rssi.current(),
+        rssi.quality(),
+        rssi.stability(),
+        rssi.std_dev(),
+    ) else {
+        return;
+    };
+
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(24.0, 16.0), egui::Sense::hover());
+    let bar_width = rect.width() / 5.0;
+    for i in 0..5 {
+        let height = rect.height() * (i + 1) as f32 / 5.0;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(rect.left() + i as f32 * bar_width, rect.bottom() - height),
+            egui::pos2(rect.left() + (i as f32 + 0.8) * bar_width, rect.bottom()),
+        );
+        let color = if i < level {
+            ui.visuals().strong_text_color()
+        } else {
+            ui.visuals().weak_text_color()
+        };
+        ui.painter().rect_filled(bar, 0.0, color);
+    }
+
+    response.on_hover_text(format!(
+        "RSSI {} dBm, {}\n{} (σ {:.1} dB)",
+        current, quality, stability, std_dev
+    ));
+}
+
+/// Bluetooth troubleshooting for the platform this was built for
+const BLUETOOTH_HELP: &str = if cfg!(target_os = "linux") {
+    "https://wiki.archlinux.org/title/Bluetooth"
+} else if cfg!(target_os = "windows") {
+    "https://support.microsoft.com/windows/fix-bluetooth-problems-in-windows-723e092f-03fa-858b-5c80-131ec3fba75c"
+} else {
+    "https://github.com/deviceplug/btleplug#platform-status"
+};
+
+/// `512 B`, `12.3 KB` or `4.5 MB`
+fn format_bytes(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    match bytes as f64 {
+        b if b < KB => format!("{} B", bytes),
+        b if b < KB * KB => format!("{:.1} KB", b / KB),
+        b => format!("{:.1} MB", b / (KB * KB)),
+    }
+}
+
+/// Yellow banner above the chart while no adapter is available, returning
+/// whether Retry was clicked
+fn no_adapter_banner(ui: &mut egui::Ui) -> bool {
+    let mut retry = false;
+    egui::Frame::none()
+        .fill(Color32::from_rgb(255, 236, 153))
+        .inner_margin(egui::Margin::same(6.0))
+        .rounding(4.0)
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.horizontal_wrapped(|ui| {
+                ui.colored_label(Color32::BLACK, format!("⚠ {}", ConnectionState::NoAdapter));
+                retry = ui.button("Retry").clicked();
+                ui.hyperlink_to("Troubleshooting", BLUETOOTH_HELP);
+            });
+        });
+    retry
+}
+
+/// ↑, → or ↓ for a change of `rate` °C per minute
+fn trend_arrow(rate: f32) -> egui::RichText {
+    let (arrow, color) = if rate > TREND_THRESHOLD {
+        ("↑", Color32::RED)
+    } else if rate < -TREND_THRESHOLD {
+        ("↓", Color32::BLUE)
+    } else {
+        ("→", Color32::GRAY)
+    };
+    egui::RichText::new(arrow).color(color).strong()
+}
+
+/// Checkbox enabling an optional threshold plus its value
+fn threshold_edit(ui: &mut egui::Ui, label: &str, threshold: &mut Option<f32>, default: f32) {
+    let mut enabled = threshold.is_some();
+    if ui.checkbox(&mut enabled, label).changed() {
+        *threshold = enabled.then_some(default);
+    }
+    if let Some(value) = threshold {
+        ui.add(egui::DragValue::new(value).speed(0.1).suffix(" °C"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ui_with_locations(locations: &[(&str, &str)]) -> UI {
+        let config = Config {
+            sensor_locations: locations
+                .iter()
+                .map(|(filter, label)| (filter.to_string(), label.to_string()))
+                .collect(),
+            ..Config::default()
+        };
+        let (_tx, events) = std::sync::mpsc::channel();
+        UI::new(
+            events,
+            config,
+            PathBuf::from("config.toml"),
+            Arc::new(Mutex::new(None)),
+            Arc::new(Metrics::default()),
+        )
+    }
+
+    #[test]
+    fn label_adds_location_of_matching_name() {
+        let ui = ui_with_locations(&[("Temperature01", "Kitchen")]);
+        assert_eq!(
+            ui.label_for("Temperature01", "00:11:22:33:44:55"),
+            "Kitchen (Temperature01)"
+        );
+        assert_eq!(
+            ui.label_for("Temperature02", "00:11:22:33:44:55"),
+            "Temperature02"
+        );
+    }
+
+    #[test]
+    fn label_falls_back_to_address() {
+        let ui = ui_with_locations(&[("00:11:22:33:44:55", "Cellar")]);
+        assert_eq!(
+            ui.label_for("", "00:11:22:33:44:55"),
+            "Cellar (00:11:22:33:44:55)"
+        );
+    }
+    #[test]
+    fn bytes_are_shown_in_readable_units() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(12_595), "12.3 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.5 MB");
+    }
+}
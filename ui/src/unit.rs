@@ -0,0 +1,124 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Unit temperatures are shown in. Readings are always kept in °C
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase", try_from = "String")]
+#[non_exhaustive]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+impl TemperatureUnit {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            TemperatureUnit::Celsius => "°C",
+            TemperatureUnit::Fahrenheit => "°F",
+        }
+    }
+
+    /// `celsius` in this unit
+    pub fn convert(self, celsius: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    /// A temperature difference, like a standard deviation, in this unit
+    pub fn convert_difference(self, celsius: f32) -> f32 {
+        match self {
+            TemperatureUnit::Celsius => celsius,
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0,
+        }
+    }
+
+    /// `23.4°C`
+    pub fn format(self, celsius: f32) -> String {
+        format!("{:.1}{}", self.convert(celsius), self.symbol())
+    }
+}
+
+/// A unit name `TemperatureUnit` doesn't know
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseTemperatureUnitError(String);
+
+impl fmt::Display for ParseTemperatureUnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown temperature unit `{}`, expected celsius, c, fahrenheit or f",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseTemperatureUnitError {}
+
+impl FromStr for TemperatureUnit {
+    type Err = ParseTemperatureUnitError;
+
+    /// `celsius`, `c`, `fahrenheit` or `f`, in any case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "celsius" | "c" => Ok(TemperatureUnit::Celsius),
+            "fahrenheit" | "f" => Ok(TemperatureUnit::Fahrenheit),
+            _ => Err(ParseTemperatureUnitError(s.to_owned())),
+        }
+    }
+}
+
+impl TryFrom<String> for TemperatureUnit {
+    type Error = ParseTemperatureUnitError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_and_abbreviations_in_any_case() {
+        for s in ["celsius", "Celsius", "CELSIUS", "c", "C"] {
+            assert_eq!(s.parse(), Ok(TemperatureUnit::Celsius), "{}", s);
+        }
+        for s in ["fahrenheit", "Fahrenheit", "FAHRENHEIT", "f", "F"] {
+            assert_eq!(s.parse(), Ok(TemperatureUnit::Fahrenheit), "{}", s);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_units() {
+        for s in ["", "kelvin", "k", "°C", " c", "celcius"] {
+            assert_eq!(
+                s.parse::<TemperatureUnit>(),
+                Err(ParseTemperatureUnitError(s.to_owned()))
+            );
+        }
+        assert_eq!(
+            "kelvin".parse::<TemperatureUnit>().unwrap_err().to_string(),
+            "unknown temperature unit `kelvin`, expected celsius, c, fahrenheit or f"
+        );
+    }
+
+    #[test]
+    fn deserializes_through_from_str() {
+        #[derive(Serialize, Deserialize)]
+        struct Table {
+            unit: TemperatureUnit,
+        }
+        let table: Table = toml::from_str("unit = \"F\"").unwrap();
+        assert_eq!(table.unit, TemperatureUnit::Fahrenheit);
+        assert!(toml::from_str::<Table>("unit = \"kelvin\"").is_err());
+
+        // written back in the long form
+        assert_eq!(toml::to_string(&table).unwrap(), "unit = \"fahrenheit\"\n");
+    }
+}
@@ -0,0 +1,39 @@
+//! Decoding and history through the library crate only. The sensor task is
+//! tested end to end against the mock adapter in `ble::tests::mock_flow`, run
+//! with `cargo test --features testing` (or `mock-ble`), since the binary's
+//! modules are out of reach from here
+
+use std::time::Instant;
+
+use ble_temperature_ui::decode::TemperatureMeasurement;
+use ble_temperature_ui::history::TemperatureHistory;
+
+/// Temperature Measurement notification of `millis` thousandths of a degree,
+/// in Fahrenheit when `fahrenheit`
+fn notification(millis: u32, fahrenheit: bool) -> Vec<u8> {
+    let mut payload = vec![fahrenheit as u8];
+    payload.extend_from_slice(&millis.to_le_bytes()[..3]);
+    // exponent byte, ignored by the decoder
+    payload.push(0xfd);
+    payload
+}
+
+#[test]
+fn fahrenheit_notifications_are_converted() {
+    // 98.6°F
+    let celsius = TemperatureMeasurement::try_from(&notification(98_600, true)[..])
+        .unwrap()
+        .celsius;
+    assert!((celsius - 37.0).abs() < 1e-3);
+}
+
+#[test]
+fn malformed_notifications_are_dropped() {
+    let mut history = TemperatureHistory::with_capacity(10);
+    for payload in [vec![], vec![0x00, 0x01], notification(21_500, false)] {
+        if let Ok(measurement) = TemperatureMeasurement::try_from(&payload[..]) {
+            history.push(Instant::now(), measurement.celsius);
+        }
+    }
+    assert_eq!(history.len(), 1);
+}